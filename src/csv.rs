@@ -0,0 +1,214 @@
+// Reading and writing of 2-D arrays as CSV
+// each row of the array is a line, each column a field separated by ,
+// fields with , " or line breaks are between " with "" for "
+// when reading, a column is Integer if all its fields are integers, Real if they are all numbers,
+// and char vectors otherwise, with Long and Double for numbers too big or precise like literals
+
+use crate::ndarray::{NDArray, SingleValue};
+use crate::token::is_double;
+
+impl NDArray {
+	// the first line is skipped if it's a header with the names of the columns
+	pub fn load_csv(path: &str, header: bool) -> Self {
+		let csv = std::fs::read_to_string(path)
+			.unwrap_or_else(|error| panic!("Cannot read {} : {}", path, error));
+		Self::from_csv(&csv, header)
+	}
+
+	pub fn save_csv(&self, path: &str) {
+		std::fs::write(path, self.to_csv())
+			.unwrap_or_else(|error| panic!("Cannot write {} : {}", path, error));
+	}
+
+	pub fn from_csv(csv: &str, header: bool) -> Self {
+		let mut rows = parse_rows(csv);
+		if header && !rows.is_empty() {
+			rows.remove(0);
+		}
+		let nb_columns = rows.first().map(Vec::len).unwrap_or(0);
+		// empty lines are rows of one empty field, only when there is one column
+		if nb_columns > 1 {
+			rows.retain(|row| row.len() > 1 || !row[0].is_empty());
+		}
+		if let Some(i) = rows.iter().position(|row| row.len() != nb_columns) {
+			panic!(
+				"Row {} of the CSV has {} fields instead of {}",
+				i + 1,
+				rows[i].len(),
+				nb_columns
+			);
+		}
+		let parsers = (0..nb_columns)
+			.map(|column| column_parser(rows.iter().map(|row| row[column].as_str())))
+			.collect::<Vec<_>>();
+		let inner = rows
+			.iter()
+			.map(|row| Self::NDArray {
+				shape: vec![nb_columns],
+				inner: row
+					.iter()
+					.zip(&parsers)
+					.map(|(field, parse)| parse(field))
+					.collect(),
+			})
+			.collect::<Vec<_>>();
+		Self::NDArray {
+			shape: vec![inner.len(), nb_columns],
+			inner,
+		}
+	}
+
+	// the elements are single values or char vectors written as text
+	pub fn to_csv(&self) -> String {
+		let rows = match self {
+			Self::NDArray { inner, .. }
+				if inner.iter().all(|x| matches!(x, Self::NDArray { .. })) =>
+			{
+				inner
+			}
+			_ => panic!("Only 2-D arrays can be written as CSV"),
+		};
+		let mut csv = String::new();
+		for row in rows {
+			let Self::NDArray { inner: row, .. } = row else {
+				unreachable!()
+			};
+			let fields = row.iter().map(|x| match x {
+				Self::SingleValue(x) => format_single_value(x),
+				// an empty char vector, as read from an empty field
+				Self::NDArray { inner, .. } if inner.is_empty() => String::new(),
+				Self::NDArray { .. } => x
+					.as_text()
+					.unwrap_or_else(|| panic!("Only 2-D arrays can be written as CSV")),
+			});
+			let fields = fields.map(|x| quote(&x)).collect::<Vec<_>>();
+			csv.push_str(&fields.join(","));
+			csv.push('\n');
+		}
+		csv
+	}
+}
+
+// reals keep a point so that their column is read back as reals
+fn format_single_value(x: &SingleValue) -> String {
+	let text = x.to_string();
+	match x {
+		SingleValue::Real(_) | SingleValue::Double(_) if text.parse::<i64>().is_ok() => text + ".0",
+		_ => text,
+	}
+}
+
+// fields that need it are put between "
+fn quote(field: &str) -> String {
+	if field.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", field.replace('"', "\"\""))
+	} else {
+		field.to_string()
+	}
+}
+
+// the fields of each line
+fn parse_rows(csv: &str) -> Vec<Vec<String>> {
+	let mut rows = Vec::new();
+	let mut row = Vec::new();
+	let mut field = String::new();
+	let mut quoted = false;
+	let mut chars = csv.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			'"' if quoted && chars.peek() == Some(&'"') => {
+				chars.next();
+				field.push('"');
+			}
+			'"' if quoted => quoted = false,
+			'"' if field.is_empty() => quoted = true,
+			_ if quoted => field.push(c),
+			',' => row.push(std::mem::take(&mut field)),
+			'\r' if chars.peek() == Some(&'\n') => {}
+			'\n' => {
+				row.push(std::mem::take(&mut field));
+				rows.push(std::mem::take(&mut row));
+			}
+			_ => field.push(c),
+		}
+	}
+	if quoted {
+		panic!("Expected closing \" in CSV");
+	}
+	if !row.is_empty() || !field.is_empty() {
+		row.push(field);
+		rows.push(row);
+	}
+	rows
+}
+
+// how the fields of a column are read, from the type all of them have
+fn column_parser<'a>(fields: impl Iterator<Item = &'a str> + Clone) -> fn(&str) -> NDArray {
+	let all = |f: fn(&str) -> bool| fields.clone().all(|x| f(x.trim()));
+	let any = |f: fn(&str) -> bool| fields.clone().any(|x| f(x.trim()));
+	if all(|x| x.parse::<i32>().is_ok()) {
+		|x| NDArray::SingleValue(SingleValue::Integer(x.trim().parse().unwrap()))
+	} else if all(|x| x.parse::<i64>().is_ok()) {
+		|x| NDArray::SingleValue(SingleValue::Long(x.trim().parse().unwrap()))
+	} else if all(|x| x.parse::<f64>().is_ok()) && any(needs_double) {
+		|x| NDArray::SingleValue(SingleValue::Double(x.trim().parse().unwrap()))
+	} else if all(|x| x.parse::<f32>().is_ok()) {
+		|x| NDArray::SingleValue(SingleValue::Real(x.trim().parse().unwrap()))
+	} else {
+		|x| NDArray::from_1d_char(x.chars().collect())
+	}
+}
+
+// reals too precise or too big for a f32
+fn needs_double(x: &str) -> bool {
+	let too_big =
+		x.parse::<f32>().is_ok_and(f32::is_infinite) && x.parse::<f64>().is_ok_and(f64::is_finite);
+	is_double(x) || too_big
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::ndarray::tests::typed;
+	use crate::ndarray::NDArray;
+
+	fn round_trip(csv: &str, header: bool) -> String {
+		NDArray::from_csv(csv, header).to_csv()
+	}
+
+	#[test]
+	fn round_trips() {
+		assert_eq!(round_trip("name,age\nbob,3\n,4", true), "bob,3\n,4\n");
+		assert_eq!(round_trip("1,2.5,x\n3,4,y\n", false), "1,2.5,x\n3,4.0,y\n");
+		assert_eq!(
+			round_trip("\"a,b\",\"say \"\"hi\"\"\"\n", false),
+			"\"a,b\",\"say \"\"hi\"\"\"\n"
+		);
+		assert_eq!(
+			round_trip("a,\"line\nbreak\"\r\n", false),
+			"a,\"line\nbreak\"\n"
+		);
+		assert_eq!(round_trip("", false), "");
+		// empty fields of a single column are rows, but not empty lines between rows of more
+		assert_eq!(round_trip("a\n\nb\n", false), "a\n\nb\n");
+		assert_eq!(round_trip("\n", false), "\n");
+		assert_eq!(round_trip("1,2\n\n3,4\n", false), "1,2\n3,4\n");
+		assert_eq!(round_trip("3000000000\n1\n", false), "3000000000\n1\n");
+		assert_eq!(round_trip("3.14159265\n1\n", false), "3.14159265\n1.0\n");
+	}
+
+	#[test]
+	fn column_types() {
+		let array = NDArray::from_csv("1,1.5,ab\n2,2,\n", false);
+		assert_eq!(
+			typed(&array),
+			"[2, 3][[3][Integer(1), Real(1.5), [2][Char('a'), Char('b')]], \
+			[3][Integer(2), Real(2.0), [0][]]]"
+		);
+		let array = NDArray::from_csv("3000000000,3.14159265,1e50\n1,0.5,2\n", false);
+		assert_eq!(
+			typed(&array),
+			"[2, 3][[3][Long(3000000000), Double(3.14159265), Double(1e50)], \
+			[3][Long(1), Double(0.5), Double(2.0)]]"
+		);
+	}
+}
@@ -0,0 +1,38 @@
+// Errors of the language are panics, the embedding API turns them into this error
+// the panic is still reported by the panic hook, which can be replaced with std::panic::set_hook
+
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::panic::{self, AssertUnwindSafe};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+	// message of the panic
+	pub message: String,
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.message)
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+	fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+		let message = match payload.downcast::<String>() {
+			Ok(message) => *message,
+			Err(payload) => match payload.downcast::<&str>() {
+				Ok(message) => message.to_string(),
+				Err(_) => String::from("Unknown error"),
+			},
+		};
+		Self { message }
+	}
+}
+
+// runs a function, with its panic as an error
+pub fn catch<T>(f: impl FnOnce() -> T) -> Result<T, Error> {
+	panic::catch_unwind(AssertUnwindSafe(f)).map_err(Error::from_panic)
+}
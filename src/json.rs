@@ -0,0 +1,406 @@
+// Conversion between arrays and JSON
+// arrays are nested JSON arrays, char vectors are strings, single chars are {"char": "c"},
+// bools are true and false and complex numbers are {"re": x, "im": y}
+// JSON strings are read as char vectors and nested arrays of the same shape as a bigger array,
+// arrays of arrays of different shapes can't be read
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::ndarray::{NDArray, SingleValue};
+
+impl NDArray {
+	pub fn to_json(&self) -> String {
+		let mut json = String::new();
+		write_value(self, &mut json);
+		json
+	}
+
+	pub fn from_json(json: &str) -> Self {
+		let mut chars = json.chars().peekable();
+		let value = read_value(&mut chars);
+		skip_whitespace(&mut chars);
+		if let Some(c) = chars.next() {
+			panic!("Unexpected {} after the JSON value", c);
+		}
+		value
+	}
+}
+
+fn write_value(value: &NDArray, json: &mut String) {
+	match value {
+		NDArray::SingleValue(x) => write_single_value(x, json),
+		NDArray::NDArray { inner, .. } => match value.as_text() {
+			Some(text) => write_string(&text, json),
+			None => {
+				json.push('[');
+				for (i, x) in inner.iter().enumerate() {
+					if i != 0 {
+						json.push(',');
+					}
+					write_value(x, json);
+				}
+				json.push(']');
+			}
+		},
+	}
+}
+
+fn write_single_value(value: &SingleValue, json: &mut String) {
+	match value {
+		SingleValue::Integer(x) => json.push_str(&x.to_string()),
+		SingleValue::Long(x) => json.push_str(&x.to_string()),
+		SingleValue::Real(x) => write_real(*x as f64, &x.to_string(), json),
+		SingleValue::Double(x) => write_real(*x, &x.to_string(), json),
+		SingleValue::Bool(x) => json.push_str(&x.to_string()),
+		SingleValue::Char(x) => {
+			json.push_str("{\"char\":");
+			write_string(&x.to_string(), json);
+			json.push('}');
+		}
+		SingleValue::Complex(re, im) => {
+			json.push_str("{\"re\":");
+			write_real(*re, &re.to_string(), json);
+			json.push_str(",\"im\":");
+			write_real(*im, &im.to_string(), json);
+			json.push('}');
+		}
+	}
+}
+
+// reals always have a point so that they are read back as reals
+fn write_real(x: f64, text: &str, json: &mut String) {
+	if !x.is_finite() {
+		panic!("Cannot write {} as JSON", text);
+	}
+	json.push_str(text);
+	if !text.contains('.') {
+		json.push_str(".0");
+	}
+}
+
+fn write_string(text: &str, json: &mut String) {
+	json.push('"');
+	for c in text.chars() {
+		match c {
+			'"' => json.push_str("\\\""),
+			'\\' => json.push_str("\\\\"),
+			'\n' => json.push_str("\\n"),
+			'\r' => json.push_str("\\r"),
+			'\t' => json.push_str("\\t"),
+			c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+			c => json.push(c),
+		}
+	}
+	json.push('"');
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+	while let Some(' ' | '\t' | '\n' | '\r') = chars.peek() {
+		chars.next();
+	}
+}
+
+fn expect(chars: &mut Peekable<Chars>, expected: char) {
+	skip_whitespace(chars);
+	match chars.next() {
+		Some(c) if c == expected => {}
+		Some(c) => panic!("Expected {} in JSON but got {}", expected, c),
+		None => panic!("Expected {} in JSON but got the end", expected),
+	}
+}
+
+fn read_value(chars: &mut Peekable<Chars>) -> NDArray {
+	skip_whitespace(chars);
+	match chars.peek() {
+		Some('[') => read_array(chars),
+		Some('{') => NDArray::SingleValue(read_object(chars)),
+		Some('"') => NDArray::from_1d_char(read_string(chars).chars().collect()),
+		Some('-' | '0'..='9') => NDArray::SingleValue(read_number(chars)),
+		Some('t' | 'f' | 'n') => {
+			let mut word = String::new();
+			while let Some(c @ 'a'..='z') = chars.peek() {
+				word.push(*c);
+				chars.next();
+			}
+			match word.as_str() {
+				"true" => NDArray::SingleValue(SingleValue::Bool(true)),
+				"false" => NDArray::SingleValue(SingleValue::Bool(false)),
+				_ => panic!("Cannot read {} from JSON", word),
+			}
+		}
+		Some(c) => panic!("Unexpected {} in JSON", c),
+		None => panic!("Expected a JSON value"),
+	}
+}
+
+fn read_array(chars: &mut Peekable<Chars>) -> NDArray {
+	expect(chars, '[');
+	let mut inner = Vec::new();
+	// shapes of the elements that are arrays
+	let mut shapes = Vec::new();
+	skip_whitespace(chars);
+	if let Some(']') = chars.peek() {
+		chars.next();
+	} else {
+		loop {
+			skip_whitespace(chars);
+			let is_array = chars.peek() == Some(&'[');
+			let value = read_value(chars);
+			if is_array {
+				shapes.push(value.shape());
+			}
+			inner.push(value);
+			skip_whitespace(chars);
+			match chars.next() {
+				Some(',') => {}
+				Some(']') => break,
+				_ => panic!("Expected , or ] in JSON array"),
+			}
+		}
+	}
+	let same_shapes = shapes.windows(2).all(|x| x[0] == x[1]);
+	if !shapes.is_empty() && (shapes.len() != inner.len() || !same_shapes) {
+		panic!("Cannot read a JSON array of arrays of different shapes");
+	}
+	NDArray::from_elements(inner)
+}
+
+// complex numbers or chars
+fn read_object(chars: &mut Peekable<Chars>) -> SingleValue {
+	expect(chars, '{');
+	let (mut re, mut im, mut character) = (None, None, None);
+	loop {
+		skip_whitespace(chars);
+		let key = read_string(chars);
+		expect(chars, ':');
+		skip_whitespace(chars);
+		match key.as_str() {
+			"re" => re = Some(read_number(chars).as_complex().unwrap().0),
+			"im" => im = Some(read_number(chars).as_complex().unwrap().0),
+			"char" => {
+				let text = read_string(chars);
+				let mut text_chars = text.chars();
+				match (text_chars.next(), text_chars.next()) {
+					(Some(c), None) => character = Some(c),
+					_ => panic!("Expected a single char in JSON, got {:?}", text),
+				}
+			}
+			_ => panic!(
+				"Cannot read a JSON object with {}, only complex numbers and chars",
+				key
+			),
+		}
+		skip_whitespace(chars);
+		match chars.next() {
+			Some(',') => {}
+			Some('}') => break,
+			_ => panic!("Expected , or }} in JSON object"),
+		}
+	}
+	match (re, im, character) {
+		(Some(re), Some(im), None) => SingleValue::Complex(re, im),
+		(None, None, Some(c)) => SingleValue::Char(c),
+		_ => panic!("Expected re and im, or char, in JSON object"),
+	}
+}
+
+fn read_string(chars: &mut Peekable<Chars>) -> String {
+	expect(chars, '"');
+	let mut string = String::new();
+	while let Some(c) = chars.next() {
+		match c {
+			'"' => return string,
+			'\\' => string.push(match chars.next() {
+				Some('n') => '\n',
+				Some('t') => '\t',
+				Some('r') => '\r',
+				Some('b') => '\u{8}',
+				Some('f') => '\u{c}',
+				Some('u') => read_unicode_escape(chars),
+				Some(c @ ('"' | '\\' | '/')) => c,
+				_ => panic!("Unknown escape in JSON string"),
+			}),
+			c => string.push(c),
+		}
+	}
+	panic!("Expected closing \" in JSON string");
+}
+
+// the four hexadecimal digits after \u, followed by a second \u for surrogate pairs
+fn read_unicode_escape(chars: &mut Peekable<Chars>) -> char {
+	let read_hex = |chars: &mut Peekable<Chars>| {
+		let hex = chars.by_ref().take(4).collect::<String>();
+		if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+			panic!("Expected 4 hexadecimal digits after \\u, got {}", hex);
+		}
+		u32::from_str_radix(&hex, 16).unwrap()
+	};
+	let high = read_hex(chars);
+	let code = if (0xD800..0xDC00).contains(&high) {
+		if chars.next() != Some('\\') || chars.next() != Some('u') {
+			panic!("Expected the second half of a surrogate pair in JSON string");
+		}
+		let low = read_hex(chars);
+		if !(0xDC00..0xE000).contains(&low) {
+			panic!(
+				"Expected a low surrogate after \\u{:04x} in JSON string, got \\u{:04x}",
+				high, low
+			);
+		}
+		0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+	} else {
+		high
+	};
+	char::from_u32(code).unwrap_or_else(|| panic!("Invalid unicode escape {:x} in JSON", code))
+}
+
+// integers are Integer or Long if they don't fit, reals are Real or Double like literals
+fn read_number(chars: &mut Peekable<Chars>) -> SingleValue {
+	let mut number = String::new();
+	while let Some(&c) = chars.peek() {
+		if !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+			break;
+		}
+		number.push(c);
+		chars.next();
+	}
+	if !number.contains(['.', 'e', 'E']) {
+		if let Ok(x) = number.parse::<i32>() {
+			return SingleValue::Integer(x);
+		}
+		if let Ok(x) = number.parse::<i64>() {
+			return SingleValue::Long(x);
+		}
+	}
+	let mantissa = number.split(['e', 'E']).next().unwrap();
+	let nb_digits = mantissa
+		.trim_start_matches(['-', '0', '.'])
+		.chars()
+		.filter(|c| c.is_ascii_digit())
+		.count();
+	let real = number
+		.parse::<f64>()
+		.unwrap_or_else(|_| panic!("Invalid number {} in JSON", number));
+	// reals written from a Real have their shortest form, which can have more digits
+	let shortest_real = (real as f32).to_string();
+	if nb_digits <= 7 || number == shortest_real || number == shortest_real + ".0" {
+		SingleValue::Real(real as f32)
+	} else {
+		SingleValue::Double(real)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::error;
+	use crate::ndarray::tests::typed;
+	use crate::ndarray::{NDArray, SingleValue};
+
+	fn round_trip(value: NDArray) {
+		let json = value.to_json();
+		assert_eq!(typed(&NDArray::from_json(&json)), typed(&value), "{}", json);
+	}
+
+	#[test]
+	fn round_trips() {
+		round_trip(NDArray::from(42));
+		round_trip(NDArray::from(-3_000_000_000i64));
+		round_trip(NDArray::from(1.5f32));
+		round_trip(NDArray::from(0.1f32));
+		round_trip(NDArray::from(3.0f32));
+		round_trip(NDArray::from(0.123456789f64));
+		round_trip(NDArray::from(true));
+		round_trip(NDArray::SingleValue(SingleValue::Complex(1.0, -2.5)));
+		round_trip(NDArray::from("line\n\"quoted\" \\ tab\t \u{1} é 😀"));
+		round_trip(NDArray::from(vec![vec![1, 2, 3], vec![4, 5, 6]]));
+		round_trip(NDArray::from(vec![
+			NDArray::from("ab"),
+			NDArray::from("cd"),
+		]));
+		round_trip(NDArray::from(vec![
+			NDArray::from("ab"),
+			NDArray::from("cde"),
+		]));
+		round_trip(NDArray::from('c'));
+		round_trip(NDArray::from(vec!['"', 'é']));
+	}
+
+	#[test]
+	fn serialize() {
+		let array = NDArray::from(vec![
+			NDArray::from(1),
+			NDArray::from(2.0f32),
+			NDArray::from("a"),
+		]);
+		assert_eq!(array.to_json(), "[1,2.0,\"a\"]");
+		let complex = NDArray::SingleValue(SingleValue::Complex(0.5, 2.0));
+		assert_eq!(complex.to_json(), "{\"re\":0.5,\"im\":2.0}");
+		assert_eq!(NDArray::from("\u{1f}").to_json(), "\"\\u001f\"");
+		assert_eq!(NDArray::from('a').to_json(), "{\"char\":\"a\"}");
+	}
+
+	#[test]
+	fn parse() {
+		let value = NDArray::from_json(" [ [1, 2] , [3,4] ] ");
+		assert_eq!(value.shape(), [2, 2]);
+		assert_eq!(value.get(&[1]).get(&[0]).get_integer(), 3);
+		assert_eq!(
+			NDArray::from_json("\"\\u00e9\\ud83d\\ude00\\/\"").get_string(),
+			"é😀/"
+		);
+		assert_eq!(NDArray::from_json("-1.5e2").get_real(), -150.0);
+		assert_eq!(
+			NDArray::from_json("{\"im\": 1, \"re\": 2}").to_json(),
+			"{\"re\":2.0,\"im\":1.0}"
+		);
+		assert_eq!(NDArray::from_json("[]").shape(), [0]);
+	}
+
+	#[test]
+	fn invalid() {
+		let error = |json: &str| {
+			error::catch(|| NDArray::from_json(json))
+				.unwrap_err()
+				.message
+		};
+		assert_eq!(
+			error("\"\\ud800\\u0041\""),
+			"Expected a low surrogate after \\ud800 in JSON string, got \\u0041"
+		);
+		assert_eq!(
+			error("\"\\ud800x\""),
+			"Expected the second half of a surrogate pair in JSON string"
+		);
+		assert_eq!(error("\"\\udc00\""), "Invalid unicode escape dc00 in JSON");
+		assert_eq!(
+			error("\"\\u+041\""),
+			"Expected 4 hexadecimal digits after \\u, got +041"
+		);
+		assert_eq!(
+			error("\"\\u41\""),
+			"Expected 4 hexadecimal digits after \\u, got 41\""
+		);
+		assert_eq!(error("[1, 2"), "Expected , or ] in JSON array");
+		assert_eq!(error("[1] 2"), "Unexpected 2 after the JSON value");
+		assert_eq!(error("null"), "Cannot read null from JSON");
+		assert_eq!(
+			error("{\"x\": 1}"),
+			"Cannot read a JSON object with x, only complex numbers and chars"
+		);
+		assert_eq!(
+			error("{\"char\": \"ab\"}"),
+			"Expected a single char in JSON, got \"ab\""
+		);
+		assert_eq!(
+			error("{\"re\": 1, \"char\": \"a\"}"),
+			"Expected re and im, or char, in JSON object"
+		);
+		// arrays of arrays have the same shape
+		let ragged = "Cannot read a JSON array of arrays of different shapes";
+		assert_eq!(error("[1, [2, 3]]"), ragged);
+		assert_eq!(error("[[1, 2], [3]]"), ragged);
+		assert_eq!(error("[[1], \"a\"]"), ragged);
+		assert_eq!(error("[[[1, 2]], [[3]]]"), ragged);
+	}
+}
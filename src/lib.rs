@@ -0,0 +1,193 @@
+// Chimera as a library, to run programs from Rust
+// let mut program = Program::try_new("2 ×")?;
+// program.push(21.into());
+// program.run()?;
+// assert_eq!(program.get_result().get_integer(), 42);
+// errors of Chimera code are panics, hosts use try_new and run which catch them as Error,
+// new and execute let them through
+// catching them needs unwinding, they abort the host when it's built with panic = "abort"
+
+mod csv;
+mod error;
+mod json;
+mod ndarray;
+mod npy;
+mod prelude;
+mod preprocessor;
+mod program;
+mod snapshot;
+mod stack;
+mod token;
+
+pub use error::Error;
+pub use ndarray::{NDArray, SingleValue};
+pub use program::{Options, Program};
+
+#[cfg(test)]
+mod tests {
+	use crate::{NDArray, Program};
+
+	#[test]
+	fn push_and_run() {
+		let mut program = Program::try_new("2 ×").unwrap();
+		program.push(21.into());
+		program.run().unwrap();
+		assert_eq!(program.values().len(), 1);
+		assert_eq!(program.get_result().get_integer(), 42);
+	}
+
+	#[test]
+	fn arrays_and_strings() {
+		let mut program = Program::new("length");
+		program.push(NDArray::from(vec![1, 2, 3]));
+		program.push("hello".into());
+		program.run().unwrap();
+		assert_eq!(program.values()[0].shape(), [3]);
+		assert_eq!(program.get_result().get_integer(), 5);
+	}
+
+	#[test]
+	fn errors() {
+		let mut program = Program::new("1 nothing 2");
+		let error = program.run().unwrap_err();
+		assert_eq!(error.message, "Unknown word nothing");
+		assert_eq!(program.current_instruction(), 1);
+		assert!(Program::try_new("#import \"nowhere.chi\"").is_err());
+	}
+
+	// the blocks and loops that were running when the error happened are left
+	#[test]
+	fn reuse_after_error() {
+		let mut program = Program::new("3 1 ⊹ (0 (1) (nothing) while) =");
+		program.run().unwrap_err();
+		assert_eq!(program.depth, 0);
+		assert_eq!(program.loops, 0);
+		assert!(program.control.is_none());
+		assert!(program.namespaces.is_empty());
+		assert_eq!(program.indices_current, vec![program.values().len()]);
+		// break isn't in the loop that failed anymore
+		assert!(crate::error::catch(|| program.execute_code("(0) (break) 1 ifelse")).is_err());
+		program.execute_code("4 5 + 2 ×");
+		assert_eq!(program.get_result().get_integer(), 18);
+	}
+
+	fn halve(program: &mut Program) {
+		let x = program.pop().unwrap().get_integer();
+		program.push((x / 2).into());
+	}
+
+	fn at_most(program: &mut Program) {
+		let a = program.pop().unwrap().get_single_value();
+		let b = program.pop().unwrap().get_single_value();
+		program.push((b <= a).into());
+	}
+
+	#[test]
+	fn native_operators() {
+		let mut program = Program::new("9 half 3 ½ 2 4 <= 1 1 + 1 print");
+		program.register_operator("half", 1, halve);
+		program.register_operator("½", 1, halve);
+		program.register_operator("<=", 2, at_most);
+		program.register_operator("+", 2, |program| {
+			program.pop();
+		});
+		assert!(program.remove_operator("print"));
+		assert!(!program.remove_operator("print"));
+		program.run().unwrap_err();
+		let values = program.values();
+		assert_eq!(values[0].get_integer(), 4);
+		assert_eq!(values[1].get_integer(), 1);
+		assert!(values[2].get_condition());
+		assert_eq!(values[3].get_integer(), 1);
+		assert_eq!(program.current_instruction(), 11);
+	}
+
+	// the blocks of = run in subprograms, which share the operators of their program
+	#[test]
+	fn native_operators_in_blocks() {
+		let mut program = Program::new("4 1 ⊹ (1[] 10 × half) =");
+		program.register_operator("half", 1, halve);
+		program.run().unwrap();
+		let result = program.get_result();
+		let halves = (0..4)
+			.map(|i| result.get(&[i]).get_integer())
+			.collect::<Vec<_>>();
+		assert_eq!(halves, vec![0, 5, 10, 15]);
+	}
+
+	#[test]
+	fn empty_stack() {
+		let mut program = Program::new("");
+		assert!(program.pop().is_none());
+		assert!(program.pop().is_none());
+		assert_eq!(program.get_code(), "");
+		let error = crate::error::catch(|| program.get_result()).unwrap_err();
+		assert_eq!(
+			error.message,
+			"The program has no result, its stack is empty"
+		);
+		program.push(1.into());
+		assert_eq!(program.pop().unwrap().get_integer(), 1);
+		assert!(program.pop().is_none());
+		assert_eq!(program.indices_current, vec![0]);
+	}
+
+	#[test]
+	fn operator_names() {
+		let error = |name: &str| {
+			let mut program = Program::new("");
+			crate::error::catch(|| program.register_operator(name, 1, halve))
+				.unwrap_err()
+				.message
+		};
+		assert_eq!(error(""), "Operators need a name");
+		assert_eq!(error("2x"), "Operator names cannot start with '2'");
+		assert_eq!(error("a b"), "Operator names cannot contain ' '");
+		for reserved in [
+			"(x", "\"", "'", "→", "←", "§", "⍝", "ℂ", "¤", "⋱", ".x", "a.b",
+		] {
+			let c = reserved.chars().find(|c| !c.is_ascii_alphabetic()).unwrap();
+			assert_eq!(
+				error(reserved),
+				format!("Operator names cannot contain {:?}", c)
+			);
+		}
+		assert_eq!(
+			error("{-"),
+			"Operator names cannot contain {-, which starts a comment"
+		);
+		assert_eq!(error("N"), "Operator name N is the one of a set");
+		assert_eq!(
+			error("half+"),
+			"Operator name half+ mixes letters with other characters"
+		);
+		assert_eq!(
+			error("+x"),
+			"Operator name +x mixes letters with other characters"
+		);
+		// glyphs sharing their first char with other ones are found by the longest
+		let mut program = Program::new("8 +½ 1 1 +");
+		program.register_operator("+½", 1, halve);
+		program.run().unwrap();
+		assert_eq!(program.values()[0].get_integer(), 4);
+		assert_eq!(program.get_result().get_integer(), 2);
+		assert!(program.remove_operator("+½"));
+		// removed glyphs aren't found anymore
+		let mut program = Program::new("8 8 +½");
+		program.register_operator("+½", 1, halve);
+		program.remove_operator("+½");
+		program.register_operator("½", 1, halve);
+		program.run().unwrap();
+		assert_eq!(program.get_result().get_integer(), 8);
+	}
+
+	#[test]
+	fn operator_arity() {
+		let mut program = Program::new("1 +");
+		let error = program.run().unwrap_err();
+		assert_eq!(
+			error.message,
+			"Operator + takes 2 values but the stack has 1"
+		);
+	}
+}
@@ -6,6 +6,63 @@ pub enum SingleValue {
 	Integer(i32),
 	Real(f32),
 	Char(char),
+	Long(i64),
+	Double(f64),
+	Bool(bool),
+	// real and imaginary parts
+	Complex(f64, f64),
+}
+
+// What integer operations do when the result doesn't fit in the type
+// the same in debug and release builds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Overflow {
+	// panics
+	Checked,
+	// wraps around the bounds of the type
+	Wrapping,
+	// clamps to the bounds of the type
+	Saturating,
+}
+
+impl Overflow {
+	// the mode of its name, for the overflow operator, the command line and snapshots
+	pub fn from_name(name: &str) -> Self {
+		match name {
+			"checked" => Self::Checked,
+			"wrapping" => Self::Wrapping,
+			"saturating" => Self::Saturating,
+			_ => panic!(
+				"Unknown overflow mode {}, expected checked, wrapping or saturating",
+				name
+			),
+		}
+	}
+
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Checked => "checked",
+			Self::Wrapping => "wrapping",
+			Self::Saturating => "saturating",
+		}
+	}
+
+	fn apply<T: Copy + Display>(
+		self,
+		x: T,
+		y: T,
+		symbol: char,
+		checked: fn(T, T) -> Option<T>,
+		wrapping: fn(T, T) -> T,
+		saturating: fn(T, T) -> T,
+	) -> T {
+		match self {
+			Self::Checked => checked(x, y)
+				.unwrap_or_else(|| panic!("Integer overflow in {} {} {}", x, symbol, y)),
+			Self::Wrapping => wrapping(x, y),
+			Self::Saturating => saturating(x, y),
+		}
+	}
 }
 
 #[derive(Clone)]
@@ -19,12 +76,17 @@ pub enum NDArray {
 
 // you can compare fully single value between them if theyre the same type
 // for ndarrays, you can just say equal or not equal
+// integers and reals of different widths are compared after promotion
 impl PartialEq for SingleValue {
 	fn eq(&self, other: &Self) -> bool {
-		match (self, other) {
+		match Self::promote(self.clone(), other.clone()) {
 			(Self::Integer(x), Self::Integer(y)) => x == y,
 			(Self::Real(x), Self::Real(y)) => x == y,
 			(Self::Char(x), Self::Char(y)) => x == y,
+			(Self::Long(x), Self::Long(y)) => x == y,
+			(Self::Double(x), Self::Double(y)) => x == y,
+			(Self::Bool(x), Self::Bool(y)) => x == y,
+			(Self::Complex(x_re, x_im), Self::Complex(y_re, y_im)) => x_re == y_re && x_im == y_im,
 			_ => false,
 		}
 	}
@@ -32,28 +94,80 @@ impl PartialEq for SingleValue {
 
 impl PartialOrd for SingleValue {
 	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-		match (self, other) {
-			(Self::Integer(x), Self::Integer(y)) => x.partial_cmp(y),
-			(Self::Real(x), Self::Real(y)) => x.partial_cmp(y),
-			(Self::Char(x), Self::Char(y)) => x.partial_cmp(y),
+		match Self::promote(self.clone(), other.clone()) {
+			(Self::Integer(x), Self::Integer(y)) => x.partial_cmp(&y),
+			(Self::Real(x), Self::Real(y)) => x.partial_cmp(&y),
+			(Self::Char(x), Self::Char(y)) => x.partial_cmp(&y),
+			(Self::Long(x), Self::Long(y)) => x.partial_cmp(&y),
+			(Self::Double(x), Self::Double(y)) => x.partial_cmp(&y),
+			(Self::Bool(x), Self::Bool(y)) => x.partial_cmp(&y),
+			// complex numbers aren't ordered, they can only be equal
+			(x @ Self::Complex(..), y @ Self::Complex(..)) if x == y => {
+				Some(std::cmp::Ordering::Equal)
+			}
+			_ => None,
+		}
+	}
+}
+
+impl SingleValue {
+	// widens the narrower of two integers (or two reals) so both have the same type
+	// integers operated with a real become reals, doubles if the integer is a long
+	// numbers operated with a complex become complex
+	// values of unrelated types are returned as is
+	pub fn promote(a: Self, b: Self) -> (Self, Self) {
+		match (a, b) {
+			(a, b) if matches!(a, Self::Complex(..)) || matches!(b, Self::Complex(..)) => {
+				match (a.as_complex(), b.as_complex()) {
+					(Some((a_re, a_im)), Some((b_re, b_im))) => {
+						(Self::Complex(a_re, a_im), Self::Complex(b_re, b_im))
+					}
+					_ => (a, b),
+				}
+			}
+			(Self::Integer(x), Self::Long(y)) => (Self::Long(x as i64), Self::Long(y)),
+			(Self::Long(x), Self::Integer(y)) => (Self::Long(x), Self::Long(y as i64)),
+			(Self::Real(x), Self::Double(y)) => (Self::Double(x as f64), Self::Double(y)),
+			(Self::Double(x), Self::Real(y)) => (Self::Double(x), Self::Double(y as f64)),
+			(Self::Integer(x), Self::Real(y)) => (Self::Real(x as f32), Self::Real(y)),
+			(Self::Real(x), Self::Integer(y)) => (Self::Real(x), Self::Real(y as f32)),
+			(Self::Integer(x), Self::Double(y)) => (Self::Double(x as f64), Self::Double(y)),
+			(Self::Double(x), Self::Integer(y)) => (Self::Double(x), Self::Double(y as f64)),
+			(Self::Long(x), Self::Real(y)) => (Self::Double(x as f64), Self::Double(y as f64)),
+			(Self::Real(x), Self::Long(y)) => (Self::Double(x as f64), Self::Double(y as f64)),
+			(Self::Long(x), Self::Double(y)) => (Self::Double(x as f64), Self::Double(y)),
+			(Self::Double(x), Self::Long(y)) => (Self::Double(x), Self::Double(y as f64)),
+			(a, b) => (a, b),
+		}
+	}
+
+	// real and imaginary parts of any number
+	pub fn as_complex(&self) -> Option<(f64, f64)> {
+		match self {
+			Self::Integer(x) => Some((*x as f64, 0.0)),
+			Self::Real(x) => Some((*x as f64, 0.0)),
+			Self::Long(x) => Some((*x as f64, 0.0)),
+			Self::Double(x) => Some((*x, 0.0)),
+			Self::Complex(re, im) => Some((*re, *im)),
 			_ => None,
 		}
 	}
+
+	pub fn get_complex(&self) -> (f64, f64) {
+		self.as_complex().unwrap_or_else(|| panic!("Not a number"))
+	}
 }
 
 impl Debug for NDArray {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
 		match self {
 			Self::SingleValue(x) => write!(f, "{:?}", x),
-			Self::NDArray { shape, inner } => {
-				write!(f, "[")?;
-				for i in 0..shape[0] {
-					if i != 0 {
-						write!(f, ", ")?;
-					}
-					write!(f, "{:?}", inner[i])?;
-				}
-				write!(f, "]\n")
+			Self::NDArray { .. } => {
+				let format = Format {
+					precision: f.precision(),
+					..Format::default()
+				};
+				write!(f, "{}", self.layout(&format))
 			}
 		}
 	}
@@ -63,29 +177,241 @@ impl Debug for NDArray {
 
 impl Display for SingleValue {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		if let Some(precision) = f.precision() {
+			return write!(f, "{}", self.format(Some(precision)));
+		}
 		match self {
 			Self::Integer(x) => write!(f, "{}", x),
 			Self::Real(x) => write!(f, "{}", x),
 			Self::Char(x) => write!(f, "{}", x),
+			Self::Long(x) => write!(f, "{}", x),
+			Self::Double(x) => write!(f, "{}", x),
+			Self::Bool(x) => write!(f, "{}", x),
+			Self::Complex(re, im) if *im < 0.0 => write!(f, "{}-{}j", re, -im),
+			Self::Complex(re, im) => write!(f, "{}+{}j", re, im),
 		}
 	}
 }
 
+// char vectors are printed as their text, other arrays as nested brackets
+// the precision of the formatter is the one of the reals
 impl Display for NDArray {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let format = Format {
+			precision: f.precision(),
+			..Format::default()
+		};
+		write!(f, "{}", self.format(&format))
+	}
+}
+
+// Values given to programs from Rust
+impl From<i32> for NDArray {
+	fn from(x: i32) -> Self {
+		Self::SingleValue(SingleValue::Integer(x))
+	}
+}
+
+impl From<f32> for NDArray {
+	fn from(x: f32) -> Self {
+		Self::SingleValue(SingleValue::Real(x))
+	}
+}
+
+impl From<i64> for NDArray {
+	fn from(x: i64) -> Self {
+		Self::SingleValue(SingleValue::Long(x))
+	}
+}
+
+impl From<f64> for NDArray {
+	fn from(x: f64) -> Self {
+		Self::SingleValue(SingleValue::Double(x))
+	}
+}
+
+impl From<bool> for NDArray {
+	fn from(x: bool) -> Self {
+		Self::SingleValue(SingleValue::Bool(x))
+	}
+}
+
+impl From<char> for NDArray {
+	fn from(x: char) -> Self {
+		Self::SingleValue(SingleValue::Char(x))
+	}
+}
+
+// strings are char vectors
+impl From<&str> for NDArray {
+	fn from(x: &str) -> Self {
+		Self::from_1d_char(x.chars().collect())
+	}
+}
+
+// vectors of values or of arrays of the same shape
+impl<T: Into<NDArray>> From<Vec<T>> for NDArray {
+	fn from(x: Vec<T>) -> Self {
+		Self::from_elements(x.into_iter().map(Into::into).collect())
+	}
+}
+
+// How arrays are printed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Format {
+	// digits after the point of reals, None for as many as needed
+	pub precision: Option<usize>,
+	// arrays with more elements than that are truncated with ...
+	pub threshold: usize,
+	// elements kept at each end of an axis when truncated
+	pub edge_items: usize,
+}
+
+impl Default for Format {
+	fn default() -> Self {
+		Self {
+			precision: None,
+			threshold: 1000,
+			edge_items: 3,
+		}
+	}
+}
+
+impl SingleValue {
+	// the value as text, with a number of digits after the point for reals if given
+	pub fn format(&self, precision: Option<usize>) -> String {
+		match (self, precision) {
+			(Self::Real(x), Some(p)) => format!("{:.*}", p, x),
+			(Self::Double(x), Some(p)) => format!("{:.*}", p, x),
+			(Self::Complex(re, im), Some(p)) if *im < 0.0 => format!("{:.*}-{:.*}j", p, re, p, -im),
+			(Self::Complex(re, im), Some(p)) => format!("{:.*}+{:.*}j", p, re, p, im),
+			_ => self.to_string(),
+		}
+	}
+}
+
+impl NDArray {
+	pub fn format(&self, format: &Format) -> String {
 		match self {
-			Self::SingleValue(x) => write!(f, "{}", x),
-			Self::NDArray { shape, inner } => {
-				write!(f, "[")?;
-				for i in 0..shape[0] {
-					if i != 0 {
-						write!(f, ", ")?;
-					}
-					write!(f, "{}", inner[i])?;
-				}
-				write!(f, "]\n")
+			Self::SingleValue(x) => x.format(format.precision),
+			Self::NDArray { .. } => self.as_text().unwrap_or_else(|| self.layout(format)),
+		}
+	}
+
+	// a vector of chars as the text it holds
+	pub fn as_text(&self) -> Option<String> {
+		match self {
+			Self::NDArray { inner, .. } if !inner.is_empty() => inner
+				.iter()
+				.map(|x| match x {
+					Self::SingleValue(SingleValue::Char(c)) => Some(*c),
+					_ => None,
+				})
+				.collect(),
+			_ => None,
+		}
+	}
+
+	// number of single values
+	pub fn size(&self) -> usize {
+		match self {
+			Self::SingleValue(_) => 1,
+			Self::NDArray { inner, .. } => inner.iter().map(Self::size).sum(),
+		}
+	}
+
+	// the array in brackets, one line per row, one blank line between planes
+	// the elements are right aligned on the widest one
+	fn layout(&self, format: &Format) -> String {
+		let truncate = self.size() > format.threshold;
+		let width = self.cell_width(format, truncate);
+		let mut text = String::new();
+		self.layout_into(format, truncate, width, 0, &mut text);
+		text
+	}
+
+	// scalars and char vectors are printed as a single cell
+	fn cell(&self, format: &Format) -> Option<String> {
+		match self {
+			Self::SingleValue(x) => Some(x.format(format.precision)),
+			Self::NDArray { .. } => self.as_text().map(|x| format!("{:?}", x)),
+		}
+	}
+
+	// the elements of an axis that are printed, None for the ones replaced by ...
+	fn shown<'a>(inner: &'a [Self], format: &Format, truncate: bool) -> Vec<Option<&'a Self>> {
+		if truncate && inner.len() > 2 * format.edge_items {
+			let end = inner.len() - format.edge_items;
+			inner[..format.edge_items]
+				.iter()
+				.map(Some)
+				.chain(std::iter::once(None))
+				.chain(inner[end..].iter().map(Some))
+				.collect()
+		} else {
+			inner.iter().map(Some).collect()
+		}
+	}
+
+	fn cell_width(&self, format: &Format, truncate: bool) -> usize {
+		match (self.cell(format), self) {
+			(Some(cell), _) => cell.chars().count(),
+			(None, Self::NDArray { inner, .. }) => Self::shown(inner, format, truncate)
+				.into_iter()
+				.flatten()
+				.map(|x| x.cell_width(format, truncate))
+				.max()
+				.unwrap_or(0),
+			(None, Self::SingleValue(_)) => unreachable!(),
+		}
+	}
+
+	// number of nested brackets around the cells
+	fn depth(&self, format: &Format) -> usize {
+		match (self.cell(format), self) {
+			(Some(_), _) => 0,
+			(None, Self::NDArray { inner, .. }) => {
+				1 + inner.first().map(|x| x.depth(format)).unwrap_or(0)
+			}
+			(None, Self::SingleValue(_)) => unreachable!(),
+		}
+	}
+
+	fn layout_into(
+		&self,
+		format: &Format,
+		truncate: bool,
+		width: usize,
+		indent: usize,
+		text: &mut String,
+	) {
+		let inner = match (self.cell(format), self) {
+			(Some(cell), _) => {
+				text.push_str(&" ".repeat(width - cell.chars().count()));
+				text.push_str(&cell);
+				return;
+			}
+			(None, Self::NDArray { inner, .. }) => inner,
+			(None, Self::SingleValue(_)) => unreachable!(),
+		};
+		// rows of cells are on a line, deeper arrays are one line per row
+		let depth = self.depth(format);
+		let separator = if depth <= 1 {
+			String::from(" ")
+		} else {
+			"\n".repeat(depth - 1) + &" ".repeat(indent + 1)
+		};
+		text.push('[');
+		for (i, x) in Self::shown(inner, format, truncate).into_iter().enumerate() {
+			if i != 0 {
+				text.push_str(&separator);
+			}
+			match x {
+				Some(x) => x.layout_into(format, truncate, width, indent + 1, text),
+				None => text.push_str("..."),
 			}
 		}
+		text.push(']');
 	}
 }
 
@@ -188,28 +514,127 @@ impl NDArray {
 		}
 	}
 
-	pub fn addition(a: SingleValue, b: SingleValue) -> NDArray {
-		NDArray::SingleValue(match (a, b) {
-			(SingleValue::Integer(x), SingleValue::Integer(y)) => SingleValue::Integer(x + y),
+	pub fn addition(a: SingleValue, b: SingleValue, overflow: Overflow) -> NDArray {
+		NDArray::SingleValue(match SingleValue::promote(a, b) {
+			(SingleValue::Integer(x), SingleValue::Integer(y)) => {
+				SingleValue::Integer(overflow.apply(
+					x,
+					y,
+					'+',
+					i32::checked_add,
+					i32::wrapping_add,
+					i32::saturating_add,
+				))
+			}
 			(SingleValue::Real(x), SingleValue::Real(y)) => SingleValue::Real(x + y),
+			(SingleValue::Long(x), SingleValue::Long(y)) => SingleValue::Long(overflow.apply(
+				x,
+				y,
+				'+',
+				i64::checked_add,
+				i64::wrapping_add,
+				i64::saturating_add,
+			)),
+			(SingleValue::Double(x), SingleValue::Double(y)) => SingleValue::Double(x + y),
+			(SingleValue::Complex(x_re, x_im), SingleValue::Complex(y_re, y_im)) => {
+				SingleValue::Complex(x_re + y_re, x_im + y_im)
+			}
 			(SingleValue::Char(x), SingleValue::Char(y)) => panic!("Cannot add two chars"),
 			_ => panic!("Cannot add different types"),
 		})
 	}
 
-	pub fn substraction(a: SingleValue, b: SingleValue) -> NDArray {
-		NDArray::SingleValue(match (a, b) {
-			(SingleValue::Integer(x), SingleValue::Integer(y)) => SingleValue::Integer(x - y),
+	pub fn substraction(a: SingleValue, b: SingleValue, overflow: Overflow) -> NDArray {
+		NDArray::SingleValue(match SingleValue::promote(a, b) {
+			(SingleValue::Integer(x), SingleValue::Integer(y)) => {
+				SingleValue::Integer(overflow.apply(
+					x,
+					y,
+					'-',
+					i32::checked_sub,
+					i32::wrapping_sub,
+					i32::saturating_sub,
+				))
+			}
 			(SingleValue::Real(x), SingleValue::Real(y)) => SingleValue::Real(x - y),
+			(SingleValue::Long(x), SingleValue::Long(y)) => SingleValue::Long(overflow.apply(
+				x,
+				y,
+				'-',
+				i64::checked_sub,
+				i64::wrapping_sub,
+				i64::saturating_sub,
+			)),
+			(SingleValue::Double(x), SingleValue::Double(y)) => SingleValue::Double(x - y),
+			(SingleValue::Complex(x_re, x_im), SingleValue::Complex(y_re, y_im)) => {
+				SingleValue::Complex(x_re - y_re, x_im - y_im)
+			}
 			(SingleValue::Char(x), SingleValue::Char(y)) => panic!("Cannot substract two chars"),
 			_ => panic!("Cannot substract different types"),
 		})
 	}
 
-	pub fn division(a: SingleValue, b: SingleValue) -> NDArray {
-		NDArray::SingleValue(match (a, b) {
-			(SingleValue::Integer(x), SingleValue::Integer(y)) => SingleValue::Integer(x / y),
+	pub fn multiplication(a: SingleValue, b: SingleValue, overflow: Overflow) -> NDArray {
+		NDArray::SingleValue(match SingleValue::promote(a, b) {
+			(SingleValue::Integer(x), SingleValue::Integer(y)) => {
+				SingleValue::Integer(overflow.apply(
+					x,
+					y,
+					'×',
+					i32::checked_mul,
+					i32::wrapping_mul,
+					i32::saturating_mul,
+				))
+			}
+			(SingleValue::Real(x), SingleValue::Real(y)) => SingleValue::Real(x * y),
+			(SingleValue::Long(x), SingleValue::Long(y)) => SingleValue::Long(overflow.apply(
+				x,
+				y,
+				'×',
+				i64::checked_mul,
+				i64::wrapping_mul,
+				i64::saturating_mul,
+			)),
+			(SingleValue::Double(x), SingleValue::Double(y)) => SingleValue::Double(x * y),
+			(SingleValue::Complex(x_re, x_im), SingleValue::Complex(y_re, y_im)) => {
+				SingleValue::Complex(x_re * y_re - x_im * y_im, x_re * y_im + x_im * y_re)
+			}
+			(SingleValue::Char(_), SingleValue::Char(_)) => panic!("Cannot multiply two chars"),
+			_ => panic!("Cannot multiply different types"),
+		})
+	}
+
+	pub fn division(a: SingleValue, b: SingleValue, overflow: Overflow) -> NDArray {
+		NDArray::SingleValue(match SingleValue::promote(a, b) {
+			(SingleValue::Integer(_), SingleValue::Integer(0))
+			| (SingleValue::Long(_), SingleValue::Long(0)) => panic!("Cannot divide by zero"),
+			(SingleValue::Integer(x), SingleValue::Integer(y)) => {
+				SingleValue::Integer(overflow.apply(
+					x,
+					y,
+					'/',
+					i32::checked_div,
+					i32::wrapping_div,
+					i32::saturating_div,
+				))
+			}
 			(SingleValue::Real(x), SingleValue::Real(y)) => SingleValue::Real(x / y),
+			(SingleValue::Long(x), SingleValue::Long(y)) => SingleValue::Long(overflow.apply(
+				x,
+				y,
+				'/',
+				i64::checked_div,
+				i64::wrapping_div,
+				i64::saturating_div,
+			)),
+			(SingleValue::Double(x), SingleValue::Double(y)) => SingleValue::Double(x / y),
+			(SingleValue::Complex(x_re, x_im), SingleValue::Complex(y_re, y_im)) => {
+				let denominator = y_re * y_re + y_im * y_im;
+				SingleValue::Complex(
+					(x_re * y_re + x_im * y_im) / denominator,
+					(x_im * y_re - x_re * y_im) / denominator,
+				)
+			}
 			(SingleValue::Char(x), SingleValue::Char(y)) => panic!("Cannot divide two chars"),
 			_ => panic!("Cannot divide different types"),
 		})
@@ -222,6 +647,16 @@ impl NDArray {
 		}
 	}
 
+	// conditions can be booleans or integers, anything but 0 being true
+	pub fn get_condition(&self) -> bool {
+		match self {
+			Self::SingleValue(SingleValue::Bool(x)) => *x,
+			Self::SingleValue(SingleValue::Integer(x)) => *x != 0,
+			Self::SingleValue(SingleValue::Long(x)) => *x != 0,
+			_ => panic!("Not a condition"),
+		}
+	}
+
 	pub fn get_char(&self) -> char {
 		match self {
 			Self::SingleValue(SingleValue::Char(x)) => *x,
@@ -229,6 +664,16 @@ impl NDArray {
 		}
 	}
 
+	// the text of a char array
+	pub fn get_string(&self) -> String {
+		match self {
+			Self::NDArray { shape, inner } if shape.len() == 1 => {
+				inner.iter().map(|x| x.get_char()).collect()
+			}
+			_ => panic!("Not a string"),
+		}
+	}
+
 	pub fn get_real(&self) -> f32 {
 		match self {
 			Self::SingleValue(SingleValue::Real(x)) => *x,
@@ -236,10 +681,222 @@ impl NDArray {
 		}
 	}
 
+	// applies a function to every single value, keeping the shape
+	pub fn map(&self, f: &impl Fn(SingleValue) -> SingleValue) -> Self {
+		match self {
+			Self::SingleValue(x) => Self::SingleValue(f(x.clone())),
+			Self::NDArray { shape, inner } => Self::NDArray {
+				shape: shape.clone(),
+				inner: inner.iter().map(|x| x.map(f)).collect(),
+			},
+		}
+	}
+
 	pub fn from_vec(inner: Vec<NDArray>) -> Self {
 		Self::NDArray {
 			shape: vec![inner.len()],
 			inner,
 		}
 	}
+
+	// elements with the same shape make a bigger array, other ones a vector of them
+	pub fn from_elements(inner: Vec<NDArray>) -> Self {
+		let mut shape = vec![inner.len()];
+		if let Some(first) = inner.first() {
+			let first_shape = first.shape();
+			if inner.iter().all(|x| x.shape() == first_shape) {
+				shape.extend(first_shape);
+			}
+		}
+		Self::NDArray { shape, inner }
+	}
+}
+
+#[cfg(test)]
+pub mod tests {
+	use super::*;
+	use crate::error;
+	use crate::program::Program;
+	use std::f64::consts::PI;
+
+	// an array with its shapes and the types of its values, to compare arrays in tests
+	pub fn typed(array: &NDArray) -> String {
+		match array {
+			NDArray::SingleValue(x) => format!("{:?}", x),
+			NDArray::NDArray { shape, inner } => {
+				let inner = inner.iter().map(typed).collect::<Vec<_>>();
+				format!("{:?}[{}]", shape, inner.join(", "))
+			}
+		}
+	}
+
+	type Operation = fn(SingleValue, SingleValue, Overflow) -> NDArray;
+
+	const OPERATIONS: [(&str, Operation); 4] = [
+		("+", NDArray::addition),
+		("-", NDArray::substraction),
+		("×", NDArray::multiplication),
+		("/", NDArray::division),
+	];
+
+	fn operate(x: SingleValue, symbol: &str, y: SingleValue, overflow: Overflow) -> SingleValue {
+		let (_, operation) = OPERATIONS.iter().find(|(x, _)| *x == symbol).unwrap();
+		operation(x, y, overflow).get_single_value()
+	}
+
+	// the integer edges with their result in each mode, None when checked overflows
+	#[test]
+	fn integer_edges() {
+		let integers = [
+			(i32::MAX, "+", 1, None, i32::MIN, i32::MAX),
+			(i32::MIN, "-", 1, None, i32::MAX, i32::MIN),
+			(i32::MAX, "×", 2, None, -2, i32::MAX),
+			(i32::MIN, "×", -1, None, i32::MIN, i32::MAX),
+			(i32::MIN, "/", -1, None, i32::MIN, i32::MAX),
+			(i32::MIN, "/", 1, Some(i32::MIN), i32::MIN, i32::MIN),
+		];
+		for (x, symbol, y, checked, wrapping, saturating) in integers {
+			let result = |overflow| {
+				error::catch(|| {
+					operate(
+						SingleValue::Integer(x),
+						symbol,
+						SingleValue::Integer(y),
+						overflow,
+					)
+				})
+				.ok()
+			};
+			assert_eq!(result(Overflow::Checked), checked.map(SingleValue::Integer));
+			assert_eq!(
+				result(Overflow::Wrapping),
+				Some(SingleValue::Integer(wrapping))
+			);
+			assert_eq!(
+				result(Overflow::Saturating),
+				Some(SingleValue::Integer(saturating))
+			);
+		}
+		let longs = [
+			(i64::MAX, "+", 1, None, i64::MIN, i64::MAX),
+			(i64::MIN, "-", 1, None, i64::MAX, i64::MIN),
+			(i64::MIN, "×", -1, None, i64::MIN, i64::MAX),
+			(i64::MIN, "/", -1, None, i64::MIN, i64::MAX),
+		];
+		for (x, symbol, y, checked, wrapping, saturating) in longs {
+			let result = |overflow| {
+				error::catch(|| {
+					operate(SingleValue::Long(x), symbol, SingleValue::Long(y), overflow)
+				})
+				.ok()
+			};
+			assert_eq!(result(Overflow::Checked), checked.map(SingleValue::Long));
+			assert_eq!(
+				result(Overflow::Wrapping),
+				Some(SingleValue::Long(wrapping))
+			);
+			assert_eq!(
+				result(Overflow::Saturating),
+				Some(SingleValue::Long(saturating))
+			);
+		}
+	}
+
+	#[test]
+	fn division_by_zero() {
+		for overflow in [Overflow::Checked, Overflow::Wrapping, Overflow::Saturating] {
+			let result = error::catch(|| {
+				operate(
+					SingleValue::Integer(1),
+					"/",
+					SingleValue::Integer(0),
+					overflow,
+				)
+			});
+			assert_eq!(result.unwrap_err().message, "Cannot divide by zero");
+		}
+	}
+
+	#[test]
+	fn overflow_operator() {
+		let run = |source| {
+			let mut program = Program::new(source);
+			program.run().map(|_| program.get_result().get_integer())
+		};
+		assert_eq!(run("1 2147483647 \"wrapping\" overflow +"), Ok(i32::MIN));
+		assert_eq!(run("\"saturating\" overflow 2 2147483647 ×"), Ok(i32::MAX));
+		// subprograms start with the mode of their program
+		assert_eq!(
+			run("\"saturating\" overflow 1 1 ⊹ (1 2147483647 +) = first"),
+			Ok(i32::MAX)
+		);
+		let error = run("\"checked\" overflow 1 2147483647 +").unwrap_err();
+		assert_eq!(error.message, "Integer overflow in 2147483647 + 1");
+		let error = run("\"sometimes\" overflow").unwrap_err();
+		assert!(error.message.starts_with("Unknown overflow mode sometimes"));
+	}
+
+	#[test]
+	fn printing() {
+		let print = |source, format: &Format| {
+			let mut program = Program::new(source);
+			program.execute();
+			program.get_result().format(format)
+		};
+		let default = Format::default();
+		assert_eq!(print("1 2 3 3 1 ^", &default), "[1 2 3]");
+		assert_eq!(print("\"hello\"", &default), "hello");
+		// grids are aligned on the widest element
+		assert_eq!(
+			print("1 20 300 4 2 2 2 ^", &default),
+			"[[  1  20]\n [300   4]]"
+		);
+		assert_eq!(print("\"ab\" \"cde\" 2 1 ^", &default), "[ \"ab\" \"cde\"]");
+		// higher ranks are planes separated by a blank line
+		assert_eq!(
+			print("1 2 3 4 5 6 7 8 2 2 2 3 ^", &default),
+			"[[[1 2]\n  [3 4]]\n\n [[5 6]\n  [7 8]]]"
+		);
+		// big arrays are truncated
+		assert_eq!(print("2000 1 ⊹", &default), "[0 0 0 ... 0 0 0]");
+		assert_eq!(print("1000 1 ⊹", &default).matches('0').count(), 1000);
+		let small = Format {
+			precision: Some(2),
+			threshold: 4,
+			edge_items: 1,
+		};
+		assert_eq!(
+			print("1 2 3 4 5 6 7 8 9 3 3 2 ^", &small),
+			"[[1 ... 3]\n ...\n [7 ... 9]]"
+		);
+		assert_eq!(print(".5 1.25 2 1 ^", &small), "[0.50 1.25]");
+		assert_eq!(print(".5 1.25 2 1 ^", &default), "[ 0.5 1.25]");
+		assert_eq!(
+			format!("{:.1}", NDArray::from(vec![0.25, 2.0])),
+			"[0.2 2.0]"
+		);
+	}
+
+	#[test]
+	fn complex_numbers() {
+		let run = |source| {
+			let mut program = Program::new(source);
+			program.run().map(|_| typed(&program.get_result()))
+		};
+		assert_eq!(run("1 2j + 3 4j + ×"), Ok("Complex(-5.0, 10.0)".into()));
+		// the top of the stack is the left operand
+		assert_eq!(run("1 2j + 1 1j - -"), Ok("Complex(-2.0, -1.0)".into()));
+		assert_eq!(run("2j 1 1j + /"), Ok("Complex(0.5, -0.5)".into()));
+		assert_eq!(run("3 4j + 3 4j + =="), Ok("Bool(true)".into()));
+		assert_eq!(run("1.5 ℂ §"), Ok("Complex(1.5, 0.0)".into()));
+		assert_eq!(run("3 4j + ℜ"), Ok("Double(3.0)".into()));
+		assert_eq!(run("3 4j + ℑ"), Ok("Double(4.0)".into()));
+		assert_eq!(run("3 4j + ‖"), Ok("Double(5.0)".into()));
+		assert_eq!(run("1 1j + ∠"), Ok(format!("Double({:?})", PI / 4.0)));
+		assert_eq!(run("2 ℑ"), Ok("Double(0.0)".into()));
+		let error = run("2j 3j <").unwrap_err();
+		assert_eq!(error.message, "Cannot order complex numbers");
+		let error = run("1 2j >").unwrap_err();
+		assert_eq!(error.message, "Cannot order complex numbers");
+	}
 }
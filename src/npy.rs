@@ -0,0 +1,575 @@
+// Reading and writing of NumPy .npy files
+// integers, reals, bools and complex numbers of any width and byte order are read,
+// unicode strings are read as char vectors, and only arrays in C order
+// arrays are written as <i4, <i8, <f4, <f8, <c16, |b1 or <U for char arrays,
+// whose char vectors are the strings, padded with \0 up to the longest one
+// empty arrays are written as <i4
+
+use crate::ndarray::{NDArray, SingleValue};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+// type of the elements, from the descr of the header
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+	Bool,
+	Signed,
+	Unsigned,
+	Float,
+	Complex,
+	// number of chars of the strings
+	Unicode(usize),
+}
+
+impl NDArray {
+	pub fn load_npy(path: &str) -> Self {
+		let bytes =
+			std::fs::read(path).unwrap_or_else(|error| panic!("Cannot read {} : {}", path, error));
+		Self::from_npy(&bytes)
+	}
+
+	pub fn save_npy(&self, path: &str) {
+		std::fs::write(path, self.to_npy())
+			.unwrap_or_else(|error| panic!("Cannot write {} : {}", path, error));
+	}
+
+	pub fn from_npy(bytes: &[u8]) -> Self {
+		if !bytes.starts_with(MAGIC) || bytes.len() < 10 {
+			panic!("Not a .npy file");
+		}
+		// the length of the header takes 2 bytes in version 1 and 4 after
+		let (header_length, header_start) = match bytes[6] {
+			1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+			2 | 3 if bytes.len() >= 12 => (
+				u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize,
+				12,
+			),
+			2 | 3 => panic!("Not a .npy file"),
+			version => panic!("Unknown .npy version {}", version),
+		};
+		let data_start = header_start + header_length;
+		if data_start > bytes.len() {
+			panic!("The .npy header is longer than the file");
+		}
+		let header = std::str::from_utf8(&bytes[header_start..data_start])
+			.unwrap_or_else(|_| panic!("Invalid .npy header"));
+		let descr = header_value(header, "descr");
+		let descr = descr.trim_matches('\'');
+		if header_value(header, "fortran_order") != "False" {
+			panic!("Only .npy files in C order can be read");
+		}
+		let shape = header_value(header, "shape")
+			.trim_matches(['(', ')'])
+			.split(',')
+			.map(str::trim)
+			.filter(|x| !x.is_empty())
+			.map(|x| {
+				x.parse::<usize>()
+					.unwrap_or_else(|_| panic!("Invalid .npy shape"))
+			})
+			.collect::<Vec<_>>();
+
+		let (little_endian, kind, size) = parse_descr(descr);
+		let data = &bytes[data_start..];
+		let nb_values = shape
+			.iter()
+			.try_fold(1usize, |product, &x| product.checked_mul(x))
+			.unwrap_or_else(|| panic!("Invalid .npy shape"));
+		if nb_values
+			.checked_mul(size)
+			.is_none_or(|length| data.len() < length)
+		{
+			panic!("The .npy file has less data than its shape");
+		}
+		// the size is 0 for empty strings
+		let mut values = (0..nb_values)
+			.map(|i| read_value(&data[i * size..(i + 1) * size], kind, little_endian));
+		nest(&mut values, &shape)
+	}
+
+	pub fn to_npy(&self) -> Vec<u8> {
+		let mut values = Vec::new();
+		flatten(self, &mut values);
+		let (shape, descr, data) = if is_text(&values) {
+			let (shape, strings) = strings(self);
+			let length = strings.iter().map(Vec::len).max().unwrap_or(0).max(1);
+			let mut data = Vec::new();
+			for string in strings {
+				for i in 0..length {
+					let c = string.get(i).map(|&c| c as u32).unwrap_or(0);
+					data.extend(c.to_le_bytes());
+				}
+			}
+			(shape, format!("<U{}", length), data)
+		} else {
+			let descr = write_descr(&values);
+			let mut data = Vec::new();
+			for x in values {
+				write_value(&x, descr, &mut data);
+			}
+			(full_shape(self), descr.to_string(), data)
+		};
+		let shape = match shape.len() {
+			1 => format!("({},)", shape[0]),
+			_ => format!(
+				"({})",
+				shape
+					.iter()
+					.map(|x| x.to_string())
+					.collect::<Vec<_>>()
+					.join(", ")
+			),
+		};
+		let header = format!(
+			"{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+			descr, shape
+		);
+		let mut bytes = write_header(header);
+		bytes.extend(data);
+		bytes
+	}
+}
+
+// the magic string, the version and the padded header
+// version 2 is only for headers too long for the 2 bytes of their length in version 1
+fn write_header(mut header: String) -> Vec<u8> {
+	// the padding adds less than 64 bytes
+	let version = if header.len() + 64 <= u16::MAX as usize {
+		1
+	} else {
+		2
+	};
+	let prefix_length = MAGIC.len() + 2 + if version == 1 { 2 } else { 4 };
+	// the data starts at a multiple of 64 bytes, the header ends with a line break
+	let padding = (64 - (prefix_length + header.len() + 1) % 64) % 64;
+	header.push_str(&" ".repeat(padding));
+	header.push('\n');
+
+	let mut bytes = MAGIC.to_vec();
+	bytes.extend([version, 0]);
+	if version == 1 {
+		bytes.extend(u16::try_from(header.len()).unwrap().to_le_bytes());
+	} else {
+		let length =
+			u32::try_from(header.len()).unwrap_or_else(|_| panic!("The .npy header is too long"));
+		bytes.extend(length.to_le_bytes());
+	}
+	bytes.extend(header.as_bytes());
+	bytes
+}
+
+// the text of a value of the header dict, like 'shape': (2, 3)
+fn header_value<'a>(header: &'a str, key: &str) -> &'a str {
+	let start = header
+		.find(&format!("'{}'", key))
+		.unwrap_or_else(|| panic!("Expected {} in the .npy header", key));
+	let value = header[start + key.len() + 2..].trim_start();
+	let value = value
+		.strip_prefix(':')
+		.unwrap_or_else(|| panic!("Expected : after {} in the .npy header", key))
+		.trim_start();
+	// tuples can have commas inside them
+	let end = if value.starts_with('(') {
+		value.find(')').map(|x| x + 1)
+	} else {
+		value.find([',', '}'])
+	};
+	value[..end.unwrap_or(value.len())].trim()
+}
+
+// byte order, kind and size in bytes of an element
+fn parse_descr(descr: &str) -> (bool, Kind, usize) {
+	let mut chars = descr.chars();
+	let little_endian = match chars.next() {
+		Some('<' | '|') => true,
+		Some('>') => false,
+		Some('=') => cfg!(target_endian = "little"),
+		_ => panic!("Unknown .npy dtype {}", descr),
+	};
+	let kind = chars.next();
+	let size = chars
+		.as_str()
+		.parse::<usize>()
+		.unwrap_or_else(|_| panic!("Unknown .npy dtype {}", descr));
+	match (kind, size) {
+		(Some('b'), 1) => (little_endian, Kind::Bool, size),
+		(Some('i'), 1..=8) => (little_endian, Kind::Signed, size),
+		(Some('u'), 1..=8) => (little_endian, Kind::Unsigned, size),
+		(Some('f'), 4 | 8) => (little_endian, Kind::Float, size),
+		(Some('c'), 8 | 16) => (little_endian, Kind::Complex, size),
+		// the size of unicode dtypes is in chars of 4 bytes
+		(Some('U'), _) => (
+			little_endian,
+			Kind::Unicode(size),
+			size.checked_mul(4)
+				.unwrap_or_else(|| panic!("Unsupported .npy dtype {}", descr)),
+		),
+		_ => panic!("Unsupported .npy dtype {}", descr),
+	}
+}
+
+// an unsigned integer of any width
+fn read_bits(bytes: &[u8], little_endian: bool) -> u64 {
+	let fold = |bits: u64, byte: &u8| (bits << 8) | *byte as u64;
+	if little_endian {
+		bytes.iter().rev().fold(0, fold)
+	} else {
+		bytes.iter().fold(0, fold)
+	}
+}
+
+fn read_float(bytes: &[u8], little_endian: bool) -> f64 {
+	let bits = read_bits(bytes, little_endian);
+	match bytes.len() {
+		4 => f32::from_bits(bits as u32) as f64,
+		8 => f64::from_bits(bits),
+		size => panic!("Unsupported .npy float of {} bytes", size),
+	}
+}
+
+// integers are Integer when they fit and Long otherwise, reals are Real or Double like their width
+fn read_value(bytes: &[u8], kind: Kind, little_endian: bool) -> NDArray {
+	let bits = read_bits(bytes, little_endian);
+	let integer = |x: i64| match i32::try_from(x) {
+		Ok(x) => SingleValue::Integer(x),
+		Err(_) => SingleValue::Long(x),
+	};
+	NDArray::SingleValue(match kind {
+		Kind::Bool => SingleValue::Bool(bits != 0),
+		Kind::Signed => {
+			// sign extension from the width of the integer
+			let shift = 64 - 8 * bytes.len() as u32;
+			integer(((bits << shift) as i64) >> shift)
+		}
+		Kind::Unsigned => {
+			integer(i64::try_from(bits).unwrap_or_else(|_| panic!("Integer {} is too big", bits)))
+		}
+		Kind::Float if bytes.len() == 4 => {
+			SingleValue::Real(read_float(bytes, little_endian) as f32)
+		}
+		Kind::Float => SingleValue::Double(read_float(bytes, little_endian)),
+		Kind::Complex => {
+			let (re, im) = bytes.split_at(bytes.len() / 2);
+			SingleValue::Complex(read_float(re, little_endian), read_float(im, little_endian))
+		}
+		// strings are padded with \0 up to the size of the dtype
+		Kind::Unicode(length) => {
+			let string = (0..length)
+				.map(|i| read_bits(&bytes[4 * i..4 * i + 4], little_endian) as u32)
+				.take_while(|&x| x != 0)
+				.map(|x| char::from_u32(x).unwrap_or_else(|| panic!("Invalid char in .npy")))
+				.collect::<Vec<char>>();
+			return NDArray::from_1d_char(string);
+		}
+	})
+}
+
+// builds an array of a shape from its values in C order
+fn nest(values: &mut impl Iterator<Item = NDArray>, shape: &[usize]) -> NDArray {
+	match shape.split_first() {
+		None => values.next().unwrap(),
+		Some((&length, rest)) => {
+			NDArray::from_elements((0..length).map(|_| nest(values, rest)).collect())
+		}
+	}
+}
+
+// the shape of an array with the ones of its elements, which have to be the same
+fn full_shape(array: &NDArray) -> Vec<usize> {
+	match array {
+		NDArray::SingleValue(_) => vec![],
+		NDArray::NDArray { inner, .. } => {
+			let element_shape = inner.first().map(full_shape).unwrap_or_default();
+			if inner.iter().any(|x| full_shape(x) != element_shape) {
+				panic!("Cannot save an array whose elements have different shapes as .npy");
+			}
+			let mut shape = vec![inner.len()];
+			shape.extend(element_shape);
+			shape
+		}
+	}
+}
+
+fn flatten(array: &NDArray, values: &mut Vec<SingleValue>) {
+	match array {
+		NDArray::SingleValue(x) => values.push(x.clone()),
+		NDArray::NDArray { inner, .. } => inner.iter().for_each(|x| flatten(x, values)),
+	}
+}
+
+fn is_text(values: &[SingleValue]) -> bool {
+	!values.is_empty() && values.iter().all(|x| matches!(x, SingleValue::Char(_)))
+}
+
+// the shape of a char array without the axis of its strings, with its strings in C order
+// its char vectors can have different lengths, but not the arrays holding them
+fn strings(array: &NDArray) -> (Vec<usize>, Vec<Vec<char>>) {
+	match array {
+		NDArray::SingleValue(_) => (vec![], vec![vec![array.get_char()]]),
+		NDArray::NDArray { inner, .. }
+			if inner.iter().all(|x| matches!(x, NDArray::SingleValue(_))) =>
+		{
+			let string = inner.iter().map(|x| x.get_char()).collect();
+			(vec![], vec![string])
+		}
+		NDArray::NDArray { inner, .. } => {
+			let elements = inner.iter().map(strings).collect::<Vec<_>>();
+			let element_shape = elements
+				.first()
+				.map(|(shape, _)| shape.clone())
+				.unwrap_or_default();
+			if elements.iter().any(|(shape, _)| *shape != element_shape) {
+				panic!("Cannot save an array whose elements have different shapes as .npy");
+			}
+			let mut shape = vec![inner.len()];
+			shape.extend(element_shape);
+			(
+				shape,
+				elements
+					.into_iter()
+					.flat_map(|(_, strings)| strings)
+					.collect(),
+			)
+		}
+	}
+}
+
+// the dtype holding all the values, which aren't chars
+// empty arrays have no element type, they are saved as integers
+fn write_descr(values: &[SingleValue]) -> &'static str {
+	let all = |f: fn(&SingleValue) -> bool| values.iter().all(f);
+	if values.is_empty() {
+		"<i4"
+	} else if all(|x| matches!(x, SingleValue::Bool(_))) {
+		"|b1"
+	} else if all(|x| matches!(x, SingleValue::Integer(_))) {
+		"<i4"
+	} else if all(|x| matches!(x, SingleValue::Integer(_) | SingleValue::Long(_))) {
+		"<i8"
+	} else if all(|x| matches!(x, SingleValue::Real(_))) {
+		"<f4"
+	} else if all(|x| x.as_complex().is_some() && !matches!(x, SingleValue::Complex(..))) {
+		"<f8"
+	} else if all(|x| x.as_complex().is_some()) {
+		"<c16"
+	} else {
+		panic!("Cannot save an array mixing chars or bools with other values as .npy");
+	}
+}
+
+fn write_value(value: &SingleValue, descr: &str, bytes: &mut Vec<u8>) {
+	match (descr, value) {
+		("|b1", SingleValue::Bool(x)) => bytes.push(*x as u8),
+		("<i4", SingleValue::Integer(x)) => bytes.extend(x.to_le_bytes()),
+		("<i8", SingleValue::Integer(x)) => bytes.extend((*x as i64).to_le_bytes()),
+		("<i8", SingleValue::Long(x)) => bytes.extend(x.to_le_bytes()),
+		("<f4", SingleValue::Real(x)) => bytes.extend(x.to_le_bytes()),
+		("<f8", x) => bytes.extend(x.get_complex().0.to_le_bytes()),
+		("<c16", x) => {
+			let (re, im) = x.get_complex();
+			bytes.extend(re.to_le_bytes());
+			bytes.extend(im.to_le_bytes());
+		}
+		_ => unreachable!(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::error;
+	use crate::ndarray::tests::typed;
+
+	// a version 1 .npy file with its header padded like numpy does
+	fn npy(descr: &str, shape: &str, data: &[u8]) -> Vec<u8> {
+		let header = format!(
+			"{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+			descr, shape
+		);
+		let header = format!("{:<1$}\n", header, 117);
+		let mut bytes = MAGIC.to_vec();
+		bytes.extend([1, 0]);
+		bytes.extend(u16::try_from(header.len()).unwrap().to_le_bytes());
+		bytes.extend(header.as_bytes());
+		bytes.extend(data);
+		bytes
+	}
+
+	fn read(descr: &str, shape: &str, data: &[u8]) -> String {
+		typed(&NDArray::from_npy(&npy(descr, shape, data)))
+	}
+
+	fn round_trip(array: NDArray) {
+		let loaded = NDArray::from_npy(&array.to_npy());
+		assert_eq!(typed(&loaded), typed(&array));
+	}
+
+	#[test]
+	fn header() {
+		let bytes = NDArray::from(vec![vec![1, 2, 3], vec![4, 5, 6]]).to_npy();
+		assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+		let header_length = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+		// the data is aligned on 64 bytes
+		assert_eq!((10 + header_length) % 64, 0);
+		assert_eq!(bytes.len(), 10 + header_length + 6 * 4);
+		let header = std::str::from_utf8(&bytes[10..10 + header_length]).unwrap();
+		assert!(header.starts_with("{'descr': '<i4', 'fortran_order': False, 'shape': (2, 3), }"));
+		assert!(header.ends_with(" \n"));
+		let header = |array: NDArray| {
+			let bytes = array.to_npy();
+			let header = String::from_utf8_lossy(&bytes[10..]).to_string();
+			header[..header.find('}').unwrap() + 1].to_string()
+		};
+		assert!(header(NDArray::from(vec![1.5f32]))
+			.contains("'descr': '<f4', 'fortran_order': False, 'shape': (1,)"));
+		assert!(header(NDArray::from(7i64))
+			.contains("'descr': '<i8', 'fortran_order': False, 'shape': ()"));
+	}
+
+	#[test]
+	fn dtypes() {
+		assert_eq!(read("|b1", "(2,)", &[1, 0]), "[2][Bool(true), Bool(false)]");
+		assert_eq!(
+			read("|i1", "(2,)", &[0xff, 0x7f]),
+			"[2][Integer(-1), Integer(127)]"
+		);
+		assert_eq!(read(">i2", "(1,)", &[0xff, 0xfe]), "[1][Integer(-2)]");
+		assert_eq!(read("<i4", "()", &(-5i32).to_le_bytes()), "Integer(-5)");
+		assert_eq!(
+			read("<i8", "(1,)", &(1i64 << 40).to_le_bytes()),
+			"[1][Long(1099511627776)]"
+		);
+		assert_eq!(read("<i8", "(1,)", &3i64.to_le_bytes()), "[1][Integer(3)]");
+		assert_eq!(read("|u1", "(1,)", &[200]), "[1][Integer(200)]");
+		assert_eq!(
+			read(">u4", "(1,)", &u32::MAX.to_be_bytes()),
+			"[1][Long(4294967295)]"
+		);
+		assert_eq!(read("<f4", "(1,)", &1.5f32.to_le_bytes()), "[1][Real(1.5)]");
+		assert_eq!(
+			read(">f8", "(1,)", &0.1f64.to_be_bytes()),
+			"[1][Double(0.1)]"
+		);
+		let mut complex = 1.0f32.to_le_bytes().to_vec();
+		complex.extend((-2.0f32).to_le_bytes());
+		assert_eq!(read("<c8", "(1,)", &complex), "[1][Complex(1.0, -2.0)]");
+		let mut complex = 0.5f64.to_le_bytes().to_vec();
+		complex.extend(3.0f64.to_le_bytes());
+		assert_eq!(read("<c16", "()", &complex), "Complex(0.5, 3.0)");
+		let matrix = read(
+			"<i4",
+			"(2, 2)",
+			&[1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0],
+		);
+		assert_eq!(
+			matrix,
+			"[2, 2][[2][Integer(1), Integer(2)], [2][Integer(3), Integer(4)]]"
+		);
+	}
+
+	#[test]
+	fn strings() {
+		let mut data = Vec::new();
+		for c in ['a', '\0', '\0', 'b', 'c', 'd'] {
+			data.extend((c as u32).to_le_bytes());
+		}
+		let loaded = NDArray::from_npy(&npy("<U3", "(2,)", &data));
+		assert_eq!(loaded.get(&[0]).get_string(), "a");
+		assert_eq!(loaded.get(&[1]).get_string(), "bcd");
+		// the strings are padded again when saved
+		let bytes = loaded.to_npy();
+		assert_eq!(&bytes[bytes.len() - data.len()..], data.as_slice());
+		assert_eq!(typed(&NDArray::from_npy(&bytes)), typed(&loaded));
+		let big_endian = data
+			.chunks(4)
+			.flat_map(|x| x.iter().rev().copied())
+			.collect::<Vec<_>>();
+		assert_eq!(read(">U3", "(2,)", &big_endian), typed(&loaded));
+	}
+
+	#[test]
+	fn round_trips() {
+		round_trip(NDArray::from(vec![vec![1, -2], vec![3, i32::MAX]]));
+		// longs that fit in 32 bits are read back as integers
+		round_trip(NDArray::from(vec![1i64 << 40, -(1i64 << 35)]));
+		round_trip(NDArray::from(vec![0.1f32, -2.5]));
+		round_trip(NDArray::from(vec![0.1f64, 1e300]));
+		round_trip(NDArray::from(vec![true, false, true]));
+		round_trip(NDArray::from(vec![NDArray::SingleValue(
+			SingleValue::Complex(1.0, -1.0),
+		)]));
+		round_trip(NDArray::from(vec![vec!["ab", "c"], vec!["", "def"]]));
+		round_trip(NDArray::from(42));
+		// empty arrays have no element type to take the dtype from
+		let empty = NDArray::from(Vec::<i32>::new());
+		let bytes = empty.to_npy();
+		assert!(String::from_utf8_lossy(&bytes).contains("'descr': '<i4'"));
+		round_trip(empty);
+		round_trip(NDArray::from(vec![Vec::<bool>::new(), vec![]]));
+	}
+
+	#[test]
+	fn long_header() {
+		// more than 65535 bytes of header need version 2
+		let header = format!(
+			"{{'descr': '<i4', 'fortran_order': False, 'shape': (2,), }}{}",
+			" ".repeat(70000)
+		);
+		let mut bytes = write_header(header);
+		assert_eq!(&bytes[..8], b"\x93NUMPY\x02\x00");
+		let header_length = u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize;
+		assert_eq!((12 + header_length) % 64, 0);
+		assert_eq!(bytes.len(), 12 + header_length);
+		bytes.extend([1, 0, 0, 0, 2, 0, 0, 0]);
+		assert_eq!(
+			typed(&NDArray::from_npy(&bytes)),
+			"[2][Integer(1), Integer(2)]"
+		);
+	}
+
+	#[test]
+	fn invalid() {
+		let error = |bytes: Vec<u8>| {
+			error::catch(|| NDArray::from_npy(&bytes))
+				.unwrap_err()
+				.message
+		};
+		let mut fortran = npy("<i4", "(1,)", &[0; 4]);
+		let position = fortran.windows(5).position(|x| x == b"False").unwrap();
+		fortran.splice(position..position + 5, b"True ".iter().copied());
+		assert_eq!(error(fortran), "Only .npy files in C order can be read");
+		assert_eq!(
+			error(npy("<i4", "(2,)", &[0; 4])),
+			"The .npy file has less data than its shape"
+		);
+		assert_eq!(
+			error(npy("<M8", "(1,)", &[0; 8])),
+			"Unsupported .npy dtype <M8"
+		);
+		assert_eq!(error(b"not a npy file".to_vec()), "Not a .npy file");
+		assert_eq!(
+			error(b"\x93NUMPY\x02\x00\x00\x00".to_vec()),
+			"Not a .npy file"
+		);
+		let mut truncated = npy("<i4", "(1,)", &[0; 4]);
+		truncated.truncate(64);
+		assert_eq!(error(truncated), "The .npy header is longer than the file");
+		assert_eq!(
+			error(npy("<i9", "(1,)", &[0; 9])),
+			"Unsupported .npy dtype <i9"
+		);
+		assert_eq!(
+			error(npy("<f2", "(1,)", &[0; 2])),
+			"Unsupported .npy dtype <f2"
+		);
+		// strings of no chars are empty
+		let empty_strings = NDArray::from_npy(&npy("<U0", "(2,)", &[]));
+		assert_eq!(empty_strings.get(&[1]).get_string(), "");
+		let ragged = NDArray::from(vec![NDArray::from(vec![1, 2]), NDArray::from(vec![3])]);
+		let message = error::catch(|| ragged.to_npy()).unwrap_err().message;
+		assert_eq!(
+			message,
+			"Cannot save an array whose elements have different shapes as .npy"
+		);
+	}
+}
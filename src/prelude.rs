@@ -0,0 +1,124 @@
+// Words every program starts with, written in Chimera
+// at : element of an array at an index, array index at
+// first : first element of an array
+// length : number of elements of an array
+// sum, product, max : of the elements of a non empty array
+// max2 : biggest of the two top elements
+// reverse : array with its elements in reverse order
+// equal : whether two arrays (strings for example) have the same elements
+// They only use the stack, so they don't change the variables of the programs
+// The prelude is run once, the programs start with a copy of its words
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::preprocessor::Preprocessor;
+use crate::program::{Options, Program};
+
+pub const PRELUDE: &str = include_str!("prelude.chi");
+
+pub fn words() -> &'static HashMap<String, String> {
+	static WORDS: OnceLock<HashMap<String, String>> = OnceLock::new();
+	WORDS.get_or_init(|| {
+		let options = Options {
+			no_prelude: true,
+			..Options::default()
+		};
+		let mut prelude = Program::with_preprocessor(PRELUDE, Preprocessor::new(), &options);
+		prelude.execute();
+		prelude.words
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::ndarray::{NDArray, SingleValue};
+	use crate::preprocessor::Preprocessor;
+	use crate::program::{Options, Program};
+
+	fn run(source: &str) -> NDArray {
+		let mut program = Program::new(source);
+		program.execute();
+		program.get_result()
+	}
+
+	fn integers(source: &str) -> Vec<i32> {
+		match run(source) {
+			NDArray::NDArray { inner, .. } => inner.iter().map(|x| x.get_integer()).collect(),
+			NDArray::SingleValue(x) => panic!("Expected an array, got {}", x),
+		}
+	}
+
+	#[test]
+	fn at() {
+		assert_eq!(run("5 1 ⊹ (1[] 3 +) = 2 at").get_integer(), 5);
+	}
+
+	#[test]
+	fn first() {
+		assert_eq!(run("5 1 ⊹ (1[] 3 +) = first").get_integer(), 3);
+	}
+
+	#[test]
+	fn length() {
+		assert_eq!(run("7 1 ⊹ length").get_integer(), 7);
+		assert_eq!(run("\"hello\" length").get_integer(), 5);
+	}
+
+	#[test]
+	fn sum() {
+		assert_eq!(run("5 1 ⊹ (1[] 1 +) = sum").get_integer(), 15);
+		assert_eq!(run(".5 1.5 2 1 ^ sum").get_real(), 2.0);
+	}
+
+	#[test]
+	fn product() {
+		assert_eq!(run("5 1 ⊹ (1[] 1 +) = product").get_integer(), 120);
+	}
+
+	#[test]
+	fn max() {
+		assert_eq!(run("3 9 2 3 1 ^ max").get_integer(), 9);
+		assert_eq!(run("4 7 max2").get_integer(), 7);
+		assert_eq!(run("7 4 max2").get_integer(), 7);
+	}
+
+	#[test]
+	fn reverse() {
+		assert_eq!(integers("1 2 3 4 4 1 ^ reverse"), vec![4, 3, 2, 1]);
+		assert_eq!(run("\"abc\" reverse").get_string(), "cba");
+	}
+
+	#[test]
+	fn equal() {
+		let equal = |source| matches!(run(source), NDArray::SingleValue(SingleValue::Bool(true)));
+		assert!(equal("\"chimera\" \"chimera\" equal"));
+		assert!(!equal("\"chimera\" \"chimero\" equal"));
+		assert!(!equal("\"chimera\" \"chim\" equal"));
+	}
+
+	#[test]
+	fn opt_out() {
+		let options = Options {
+			no_prelude: true,
+			..Options::default()
+		};
+		let program = Program::with_preprocessor("", Preprocessor::new(), &options);
+		assert!(program.words.is_empty());
+		assert!(Program::new("").words.contains_key("sum"));
+	}
+
+	#[test]
+	fn no_variables() {
+		let source = "1 2 3 3 1 ^ © reverse equal 4 7 max2 \"abc\" reverse 1 at 5 1 ⊹ max";
+		let mut program = Program::new(source);
+		program.execute();
+		assert_eq!(program.values().len(), 4);
+		assert!(program.variables.is_empty());
+	}
+
+	#[test]
+	fn run_once() {
+		assert!(std::ptr::eq(super::words(), super::words()));
+	}
+}
@@ -0,0 +1,586 @@
+// Textual macros applied to the source before tokenizing
+// They are defined one per line with
+// #define NAME replacement
+// #define NAME(a, b) replacement using a and b
+// and the definition lines are removed from the code
+// Macros aren't expanded inside strings, char literals and comments
+// Other files can be imported one per line with
+// #import "path/to/file.chi"
+// #import "path/to/file.chi" as namespace
+// their definitions are then available as namespace.name,
+// the namespace being the name of the file if not given
+
+use std::path::{Path, PathBuf};
+
+const MAX_EXPANSION_DEPTH: usize = 64;
+
+#[derive(Clone, Debug)]
+pub struct TextMacro {
+	pub name: String,
+	// None for macros without parenthesis, which are replaced as is
+	pub parameters: Option<Vec<String>>,
+	pub body: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Import {
+	pub path: String,
+	pub namespace: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Preprocessor {
+	pub macros: Vec<TextMacro>,
+	// imports found while defining, the program loads them
+	pub imports: Vec<Import>,
+	// directories where imports are searched after the one of the importing file
+	pub search_path: Vec<PathBuf>,
+	// directory of the file being preprocessed, None for code that isn't from a file
+	pub directory: Option<PathBuf>,
+	// files being imported, from the first one, to detect cycles
+	pub importing: Vec<PathBuf>,
+}
+
+impl Preprocessor {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	// a preprocessor for a file, with the same search path
+	pub fn for_file(&self, path: &Path) -> Self {
+		let mut importing = self.importing.clone();
+		importing.push(path.to_path_buf());
+		Self {
+			macros: Vec::new(),
+			imports: Vec::new(),
+			search_path: self.search_path.clone(),
+			directory: path.parent().map(Path::to_path_buf),
+			importing,
+		}
+	}
+
+	// finds an imported file, relative to the importing file then in the search path
+	pub fn resolve(&self, path: &str) -> PathBuf {
+		let relative = match &self.directory {
+			Some(directory) => directory.join(path),
+			None => PathBuf::from(path),
+		};
+		let found = std::iter::once(relative)
+			.chain(
+				self.search_path
+					.iter()
+					.map(|directory| directory.join(path)),
+			)
+			.find(|candidate| candidate.is_file())
+			.unwrap_or_else(|| panic!("Cannot find imported file {}", path));
+		let found = found.canonicalize().unwrap_or(found);
+		if self.importing.contains(&found) {
+			let cycle = self
+				.importing
+				.iter()
+				.chain(std::iter::once(&found))
+				.map(|x| x.display().to_string())
+				.collect::<Vec<_>>();
+			panic!("Import cycle : {}", cycle.join(" -> "));
+		}
+		found
+	}
+
+	// the macros with their names, and the references between them, in a namespace
+	pub fn namespaced(&self, namespace: &str) -> Vec<TextMacro> {
+		let renames = self
+			.macros
+			.iter()
+			.map(|x| TextMacro {
+				name: x.name.clone(),
+				parameters: None,
+				body: format!("{}.{}", namespace, x.name),
+			})
+			.collect::<Vec<_>>();
+		self.macros
+			.iter()
+			.map(|x| TextMacro {
+				name: format!("{}.{}", namespace, x.name),
+				parameters: x.parameters.clone(),
+				body: renames.iter().fold(x.body.clone(), |body, rename| {
+					expand_macro(&body, rename).unwrap_or(body)
+				}),
+			})
+			.collect()
+	}
+
+	// loads the definitions of a macro file, anything else in the file is ignored
+	pub fn load(&mut self, path: &str) {
+		let source = std::fs::read_to_string(path)
+			.unwrap_or_else(|error| panic!("Cannot read macro file {} : {}", path, error));
+		self.define(&source);
+	}
+
+	// defines the macros of the #define lines, keeps the #import lines for later
+	// and returns the code without them
+	// lines starting inside a string or a comment aren't directives
+	pub fn define(&mut self, source: &str) -> String {
+		let literals = literals(&source.chars().collect::<Vec<char>>());
+		let mut code = String::new();
+		let mut position = 0;
+		for line in source.split_inclusive('\n') {
+			let line_start = line.trim_start();
+			let start = position + line.chars().count() - line_start.chars().count();
+			position += line.chars().count();
+			if literals.get(start) == Some(&true) {
+				code.push_str(line);
+			} else if let Some(definition) = directive(line_start, "#define") {
+				self.macros.push(parse_definition(definition));
+			} else if let Some(import) = directive(line_start, "#import") {
+				self.imports.push(parse_import(import));
+			} else {
+				code.push_str(line);
+			}
+		}
+		code
+	}
+
+	// replaces the macros until there's nothing left to expand
+	pub fn expand(&self, code: &str) -> String {
+		let mut code = code.to_string();
+		for _ in 0..MAX_EXPANSION_DEPTH {
+			let mut expanded = false;
+			for text_macro in self.macros.iter() {
+				if let Some(new_code) = expand_macro(&code, text_macro) {
+					code = new_code;
+					expanded = true;
+				}
+			}
+			if !expanded {
+				return code;
+			}
+		}
+		panic!("Macro expansion is too deep, a macro probably expands to itself");
+	}
+}
+
+// the rest of the line of a directive, whose name is followed by whitespace
+fn directive<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+	let rest = line.strip_prefix(name)?;
+	if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+		Some(rest)
+	} else {
+		None
+	}
+}
+
+fn parse_definition(definition: &str) -> TextMacro {
+	let definition = definition.trim();
+	let name_end = definition
+		.find(|c: char| c.is_whitespace() || c == '(')
+		.unwrap_or(definition.len());
+	let name = definition[..name_end].to_string();
+	if name.is_empty() {
+		panic!("Expected a macro name after #define");
+	}
+	let rest = &definition[name_end..];
+	let (parameters, body) = match rest.strip_prefix('(') {
+		Some(rest) => {
+			let end = rest
+				.find(')')
+				.unwrap_or_else(|| panic!("Expected ) after the parameters of macro {}", name));
+			let parameters = rest[..end]
+				.split(',')
+				.map(|x| x.trim().to_string())
+				.filter(|x| !x.is_empty())
+				.collect();
+			(Some(parameters), &rest[end + 1..])
+		}
+		None => (None, rest),
+	};
+	TextMacro {
+		name,
+		parameters,
+		body: body.trim().to_string(),
+	}
+}
+
+fn parse_import(import: &str) -> Import {
+	let import = import.trim();
+	let (path, rest) = import
+		.strip_prefix('"')
+		.and_then(|x| x.split_once('"'))
+		.unwrap_or_else(|| panic!("Expected a path between \" after #import"));
+	let namespace = match rest.trim().strip_prefix("as") {
+		Some(namespace) => namespace.trim().to_string(),
+		None if rest.trim().is_empty() => Path::new(path)
+			.file_stem()
+			.map(|x| x.to_string_lossy().to_string())
+			.unwrap_or_else(|| panic!("Cannot name the namespace of {}", path)),
+		None => panic!("Expected as <namespace> after the path of #import"),
+	};
+	if namespace.is_empty() {
+		panic!("Expected a namespace after as");
+	}
+	Import {
+		path: path.to_string(),
+		namespace,
+	}
+}
+
+fn is_name_char(c: char) -> bool {
+	c.is_alphanumeric() || c == '_'
+}
+
+// whether the char at this index of the code is part of a name
+// a . is when the start of a name follows, so that namespace.name is a single name
+fn is_name_char_at(code: &[char], i: usize) -> bool {
+	match code[i] {
+		'.' => code
+			.get(i + 1)
+			.is_some_and(|&c| c.is_alphabetic() || c == '_'),
+		c => is_name_char(c),
+	}
+}
+
+// whether a name is at this position of the code and isn't part of a longer name
+pub(crate) fn is_name_at(code: &[char], name: &[char], i: usize) -> bool {
+	if i + name.len() > code.len() || code[i..i + name.len()] != *name {
+		return false;
+	}
+	let after = i + name.len();
+	let before_ok = i == 0 || !is_name_char(name[0]) || !is_name_char_at(code, i - 1);
+	let after_ok =
+		after == code.len() || !is_name_char(name[name.len() - 1]) || !is_name_char_at(code, after);
+	before_ok && after_ok
+}
+
+// the first occurrence of a name from an index, outside of the literals of the code
+fn find_name(code: &[char], literals: &[bool], name: &[char], from: usize) -> Option<usize> {
+	(from..code.len()).find(|&i| !literals[i] && is_name_at(code, name, i))
+}
+
+// whether each char of the code is part of a string, a char literal or a comment,
+// which are read like the lexer does, up to the end of the code if they aren't closed
+pub(crate) fn literals(code: &[char]) -> Vec<bool> {
+	let mut literals = vec![false; code.len()];
+	let mut i = 0;
+	while i < code.len() {
+		let end = match code[i] {
+			'"' => string_end(code, i + 1),
+			'r' if (i == 0 || !is_name_char_at(code, i - 1))
+				&& matches!(code.get(i + 1), Some('"' | '#')) =>
+			{
+				raw_string_end(code, i + 1)
+			}
+			'\'' if code.get(i + 1) == Some(&'\\') => {
+				// the escaped char can be a ', the literal ends at the next one
+				let closing = (i + 3..code.len()).find(|&j| code[j] == '\'');
+				closing.map(|j| j + 1).unwrap_or(code.len())
+			}
+			'\'' => (i + 3).min(code.len()),
+			'⍝' => (i..code.len())
+				.find(|&j| code[j] == '\n')
+				.unwrap_or(code.len()),
+			'{' if code.get(i + 1) == Some(&'-') => block_comment_end(code, i + 2),
+			_ => {
+				i += 1;
+				continue;
+			}
+		};
+		literals[i..end].fill(true);
+		i = end;
+	}
+	literals
+}
+
+// index after the closing " of a string, from the index after its opening "
+fn string_end(code: &[char], mut i: usize) -> usize {
+	while i < code.len() {
+		match code[i] {
+			'\\' => i += 2,
+			'"' => return i + 1,
+			_ => i += 1,
+		}
+	}
+	code.len()
+}
+
+// index after the end of a raw string r#"..."#, from the index after its r
+fn raw_string_end(code: &[char], start: usize) -> usize {
+	let nb_hashes = code[start..].iter().take_while(|&&c| c == '#').count();
+	let closing = std::iter::once('"')
+		.chain(std::iter::repeat_n('#', nb_hashes))
+		.collect::<Vec<char>>();
+	let from = start + nb_hashes + 1;
+	(from..code.len())
+		.find(|&i| code[i..].starts_with(&closing))
+		.map(|i| i + closing.len())
+		.unwrap_or(code.len())
+}
+
+// index after the -} of a block comment, from the index after its {-
+fn block_comment_end(code: &[char], mut i: usize) -> usize {
+	let mut depth = 1;
+	while i < code.len() {
+		if code[i..].starts_with(&['{', '-']) {
+			depth += 1;
+			i += 2;
+		} else if code[i..].starts_with(&['-', '}']) {
+			depth -= 1;
+			i += 2;
+			if depth == 0 {
+				return i;
+			}
+		} else {
+			i += 1;
+		}
+	}
+	code.len()
+}
+
+// splits the arguments of a call starting right after its (
+// returns them with the index right after the closing )
+// , and parenthesis inside literals don't count
+fn parse_arguments(code: &[char], literals: &[bool], start: usize) -> (Vec<String>, usize) {
+	let mut arguments = vec![String::new()];
+	let mut nb_parenthesis = 0;
+	let mut i = start;
+	while i < code.len() {
+		match code[i] {
+			_ if literals[i] => {}
+			'(' => nb_parenthesis += 1,
+			')' if nb_parenthesis == 0 => {
+				let arguments = arguments.into_iter().map(|x| x.trim().to_string());
+				return (arguments.filter(|x| !x.is_empty()).collect(), i + 1);
+			}
+			')' => nb_parenthesis -= 1,
+			',' if nb_parenthesis == 0 => {
+				arguments.push(String::new());
+				i += 1;
+				continue;
+			}
+			_ => {}
+		}
+		arguments.last_mut().unwrap().push(code[i]);
+		i += 1;
+	}
+	panic!("Expected ) after macro arguments");
+}
+
+// replaces all the parameters in the body of a macro at once, outside of its literals
+fn substitute(body: &[char], parameters: &[String], arguments: &[String]) -> String {
+	let parameters = parameters
+		.iter()
+		.map(|x| x.chars().collect::<Vec<char>>())
+		.collect::<Vec<_>>();
+	let body_literals = literals(body);
+	let mut result = String::new();
+	let mut i = 0;
+	'body: while i < body.len() {
+		for (parameter, argument) in parameters.iter().zip(arguments.iter()) {
+			if !body_literals[i] && is_name_at(body, parameter, i) {
+				result.push_str(argument);
+				i += parameter.len();
+				continue 'body;
+			}
+		}
+		result.push(body[i]);
+		i += 1;
+	}
+	result
+}
+
+// expands every occurrence of a macro, returns None if there were none
+fn expand_macro(code: &str, text_macro: &TextMacro) -> Option<String> {
+	let code = code.chars().collect::<Vec<char>>();
+	let name = text_macro.name.chars().collect::<Vec<char>>();
+	let literals = literals(&code);
+	let mut result = String::new();
+	let mut expanded = false;
+	let mut i = 0;
+	while let Some(position) = find_name(&code, &literals, &name, i) {
+		let after = position + name.len();
+		result.extend(&code[i..position]);
+		match &text_macro.parameters {
+			None => {
+				result.push_str(&text_macro.body);
+				i = after;
+			}
+			// a macro with parameters is only expanded when called
+			Some(_) if code.get(after) != Some(&'(') => {
+				result.extend(&name);
+				i = after;
+				continue;
+			}
+			Some(parameters) => {
+				let (arguments, end) = parse_arguments(&code, &literals, after + 1);
+				if arguments.len() != parameters.len() {
+					panic!(
+						"Macro {} takes {} arguments but got {}",
+						text_macro.name,
+						parameters.len(),
+						arguments.len()
+					);
+				}
+				let body = text_macro.body.chars().collect::<Vec<char>>();
+				let body_expanded = substitute(&body, parameters, &arguments);
+				result.push_str(&body_expanded);
+				i = end;
+			}
+		}
+		expanded = true;
+	}
+	result.extend(&code[i..]);
+	if expanded {
+		Some(result)
+	} else {
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::PathBuf;
+
+	use super::*;
+	use crate::program::{Options, Program};
+
+	// a directory of its own for each test, removed at the end of the test even if it panics
+	struct Directory(PathBuf);
+
+	impl Drop for Directory {
+		fn drop(&mut self) {
+			std::fs::remove_dir_all(&self.0).ok();
+		}
+	}
+
+	// with the files given
+	fn directory(name: &str, files: &[(&str, &str)]) -> Directory {
+		let directory =
+			std::env::temp_dir().join(format!("chimera_{}_{}", name, std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		for (file, source) in files {
+			std::fs::write(directory.join(file), source).unwrap();
+		}
+		Directory(directory)
+	}
+
+	fn run(path: PathBuf, preprocessor: Preprocessor) -> Program {
+		let path = path.to_str().unwrap();
+		let mut program = Program::load_with_preprocessor(path, preprocessor, &Options::default());
+		program.execute();
+		program
+	}
+
+	fn process(source: &str) -> String {
+		let mut preprocessor = Preprocessor::new();
+		let code = preprocessor.define(source);
+		preprocessor.expand(&code)
+	}
+
+	#[test]
+	fn macros() {
+		assert_eq!(process("#define TEN 10\nTEN TEN +"), "10 10 +");
+		assert_eq!(
+			process("#define ADD(a, b) a b +\nADD(1, ADD(2, 3))"),
+			"1 2 3 + +"
+		);
+		// only whole names are replaced
+		assert_eq!(process("#define X 5\nX XY Y_X X.Y"), "5 XY Y_X X.Y");
+		// a . only joins names, a number can follow a name
+		assert_eq!(process("#define X 5\nX.25 X. 2.X"), "5.25 5. 2.X");
+		// a macro with parameters is only expanded when called
+		assert_eq!(process("#define F(x) x x\nF F(1)"), "F 1 1");
+	}
+
+	#[test]
+	fn macros_outside_literals() {
+		let source = "#define X 5\n\"X\" 'X' r\"X\" r#\"\"X\"\"# ⍝ X\n{- X {- X -} X -} X";
+		assert_eq!(
+			process(source),
+			"\"X\" 'X' r\"X\" r#\"\"X\"\"# ⍝ X\n{- X {- X -} X -} 5"
+		);
+		assert_eq!(
+			process("#define X 5\n\"a\\\"X\" '\\'' X"),
+			"\"a\\\"X\" '\\'' 5"
+		);
+		// the parameters of the body aren't replaced in its literals either
+		assert_eq!(process("#define SAY(x) \"x\" x\nSAY(1)"), "\"x\" 1");
+		// nor are , and parenthesis of the arguments
+		assert_eq!(process("#define FIRST(a, b) a\nFIRST(\",)\", 2)"), "\",)\"");
+	}
+
+	#[test]
+	fn directives() {
+		let mut preprocessor = Preprocessor::new();
+		let code = preprocessor.define("#defineX 1\n  #define Y 2\n#importer\n#define\tZ 3\n");
+		assert_eq!(code, "#defineX 1\n#importer\n");
+		let names = preprocessor
+			.macros
+			.iter()
+			.map(|x| x.name.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(names, ["Y", "Z"]);
+		assert!(preprocessor.imports.is_empty());
+		// lines of multi-line strings and comments are kept
+		let source =
+			"\"a\n#define X 1\n\" r\"\n#import \"b.chi\"\n\" {-\n#define Y 2\n-}\n#define Z 3\nZ";
+		let mut preprocessor = Preprocessor::new();
+		let code = preprocessor.define(source);
+		assert_eq!(
+			code,
+			"\"a\n#define X 1\n\" r\"\n#import \"b.chi\"\n\" {-\n#define Y 2\n-}\nZ"
+		);
+		assert_eq!(preprocessor.macros.len(), 1);
+		assert!(preprocessor.imports.is_empty());
+		let mut program = Program::new("\"a\n#define X 1\nX\" print");
+		program.execute();
+		assert_eq!(program.get_code(), "\"a\n#define X 1\nX\" print");
+	}
+
+	#[test]
+	fn namespaces() {
+		let directory = directory(
+			"namespaces",
+			&[
+				("math.chi", "#define TEN 10\n(1 +) \"inc\" ≝ (inc inc) \"inc2\" ≝ 7 →seven"),
+				("main.chi", "#import \"math.chi\"\n#import \"math.chi\" as m\nmath.TEN math.inc2 m.inc ←math.seven +"),
+			],
+		);
+		let program = run(directory.0.join("main.chi"), Preprocessor::new());
+		assert_eq!(program.get_result().get_integer(), 20);
+		assert!(program.words.contains_key("math.inc"));
+		assert!(program.words.contains_key("m.inc2"));
+		assert!(!program.words.contains_key("inc"));
+	}
+
+	#[test]
+	fn search_path() {
+		let library = directory("search_path_library", &[("lib.chi", "(2 ×) \"double\" ≝")]);
+		let directory = directory(
+			"search_path",
+			&[("main.chi", "#import \"lib.chi\"\n21 lib.double")],
+		);
+		let preprocessor = Preprocessor {
+			search_path: vec![library.0.clone()],
+			..Preprocessor::new()
+		};
+		let program = run(directory.0.join("main.chi"), preprocessor);
+		assert_eq!(program.get_result().get_integer(), 42);
+	}
+
+	#[test]
+	#[should_panic(expected = "Import cycle")]
+	fn import_cycle() {
+		let directory = directory(
+			"import_cycle",
+			&[
+				("a.chi", "#import \"b.chi\"\n1"),
+				("b.chi", "#import \"a.chi\"\n2"),
+			],
+		);
+		run(directory.0.join("a.chi"), Preprocessor::new());
+	}
+
+	#[test]
+	#[should_panic(expected = "Cannot find imported file nowhere.chi")]
+	fn missing_import() {
+		Program::new("#import \"nowhere.chi\"");
+	}
+}
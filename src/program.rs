@@ -1,36 +1,155 @@
-use crate::ndarray::{NDArray, SingleValue};
+use crate::error::{self, Error};
+use crate::ndarray::{Format, NDArray, Overflow, SingleValue};
+use crate::prelude;
+use crate::preprocessor::{Import, Preprocessor};
 use crate::stack::Stack;
+use std::collections::HashMap;
 use std::mem;
 use std::ops::Deref;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use crate::token::*;
 
-#[derive(Debug)]
+// Stops the execution of the code of a loop, until the loop handles it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Control {
+	Break,
+	Continue,
+}
+
+// How a program is built, the files it imports are built the same way
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+	// unknown characters in the code are errors instead of warnings
+	pub strict: bool,
+	// programs start without the words of the prelude
+	pub no_prelude: bool,
+	// files whose #define are loaded before the program, by load_with
+	pub macro_files: Vec<String>,
+	// directories where imports are searched after the directory of the file, by load_with
+	pub search_path: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
 pub struct Program {
-	pub stack: Stack,
-	pub current_instruction: usize,
-	pub indices_current: Vec<usize>,
+	pub(crate) stack: Stack,
+	pub(crate) current_instruction: usize,
+	pub(crate) indices_current: Vec<usize>,
+	// how +, -, × and / behave when an integer overflows, set with the overflow operator
+	pub(crate) overflow: Overflow,
+	// words defined with ≝, from their name to their code
+	pub(crate) words: HashMap<String, String>,
+	// variables stored with →name and loaded with ←name
+	// subprograms start with a copy of them, so their writes don't propagate back
+	pub(crate) variables: HashMap<String, NDArray>,
+	// real time macros defined with ≜, from their name to the code generating their expansion
+	pub(crate) macros: HashMap<String, String>,
+	// namespaces of the imported words being executed, the innermost last
+	// names used inside them refer first to the definitions of their namespace
+	pub(crate) namespaces: Vec<String>,
+	// set by break and continue
+	pub(crate) control: Option<Control>,
+	// number of loop bodies being executed, break and continue are errors outside of them
+	// subprograms of ifelse are in the loops of their program, the ones of = and ¨ aren't
+	pub(crate) loops: usize,
+	// whether unknown characters in the code are errors or warnings
+	pub(crate) lexing: Lexing,
+	// how print shows arrays
+	pub(crate) format: Format,
+	// native operators the code can use, from their name or glyph
+	// shared with the subprograms, copied when the program changes them
+	pub(crate) operators: Rc<Operators>,
+	// number of blocks being executed inside the code of the program,
+	// subprograms being inside the code of the program that runs them
+	pub(crate) depth: usize,
 }
 
 impl Program {
 	pub fn new(source: &str) -> Self {
+		Self::with_preprocessor(source, Preprocessor::new(), &Options::default())
+	}
+
+	// the preprocessor can come with macros already defined, from a macro file for example
+	pub(crate) fn with_preprocessor(
+		source: &str,
+		mut preprocessor: Preprocessor,
+		options: &Options,
+	) -> Self {
+		Self::build(source, &mut preprocessor, options)
+	}
+
+	fn build(source: &str, preprocessor: &mut Preprocessor, options: &Options) -> Self {
 		let mut stack = Stack::new();
-		let mut code = String::from(source);
-		Self::preprocess(&mut code);
+		let code = preprocessor.define(source);
+		let imports = mem::take(&mut preprocessor.imports);
+		let modules = imports
+			.iter()
+			.map(|import| {
+				let module = Self::import(import, preprocessor, options);
+				(&import.namespace, module)
+			})
+			.collect::<Vec<_>>();
+		let code = preprocessor.expand(&code);
 		let code = code.chars().collect::<Vec<char>>();
 		let indices_current = vec![0];
 		stack.push(NDArray::from_1d_char(code));
-		Self {
+		let mut program = Self {
 			stack,
 			current_instruction: 0,
 			indices_current,
+			overflow: Overflow::Checked,
+			words: HashMap::new(),
+			variables: HashMap::new(),
+			macros: HashMap::new(),
+			namespaces: Vec::new(),
+			control: None,
+			loops: 0,
+			lexing: if options.strict {
+				Lexing::Strict
+			} else {
+				Lexing::Lenient
+			},
+			format: Format::default(),
+			operators: Rc::new(builtin_operators()),
+			depth: 0,
+		};
+		if !options.no_prelude {
+			program.words = prelude::words().clone();
+		}
+		for (namespace, mut module) in modules {
+			// the prelude words of the module are already there
+			module
+				.words
+				.retain(|name, code| program.words.get(name) != Some(code));
+			program.words.extend(qualify(namespace, module.words));
+			program
+				.variables
+				.extend(qualify(namespace, module.variables));
+			program.macros.extend(qualify(namespace, module.macros));
 		}
+		program
+	}
+
+	// executes an imported file to get its definitions
+	// its text macros are added to the importer's preprocessor
+	fn import(import: &Import, preprocessor: &mut Preprocessor, options: &Options) -> Self {
+		let path = preprocessor.resolve(&import.path);
+		let source = std::fs::read_to_string(&path)
+			.unwrap_or_else(|error| panic!("Cannot read {} : {}", path.display(), error));
+		let mut module_preprocessor = preprocessor.for_file(&path);
+		let mut module = Self::build(&source, &mut module_preprocessor, options);
+		module.execute();
+		preprocessor
+			.macros
+			.extend(module_preprocessor.namespaced(&import.namespace));
+		module
 	}
 
 	//same stack, different code, same current instruction
-	pub fn subprogram(code: &str, parent_program: &Self) -> Self {
-		let mut code = String::from(code);
-		Self::preprocess(&mut code);
+	// the code of a block was preprocessed with the code of its program
+	pub(crate) fn subprogram(code: &str, parent_program: &Self) -> Self {
 		let code = code.chars().collect::<Vec<char>>();
 		let mut new_stack = Stack::new();
 		new_stack.push(NDArray::from_1d_char(code));
@@ -44,6 +163,17 @@ impl Program {
 			stack: new_stack,
 			current_instruction: 0,
 			indices_current,
+			overflow: parent_program.overflow,
+			words: parent_program.words.clone(),
+			variables: parent_program.variables.clone(),
+			macros: parent_program.macros.clone(),
+			namespaces: parent_program.namespaces.clone(),
+			control: None,
+			loops: 0,
+			lexing: parent_program.lexing,
+			format: parent_program.format,
+			operators: Rc::clone(&parent_program.operators),
+			depth: parent_program.depth + 1,
 		}
 	}
 
@@ -52,27 +182,45 @@ impl Program {
 		self.indices_current[0] += 1;
 	}
 
+	// the code of the program at the bottom of the stack isn't a value
 	pub fn pop(&mut self) -> Option<NDArray> {
+		if self.values().is_empty() {
+			return None;
+		}
 		self.indices_current[0] -= 1;
 		self.stack.pop()
 	}
 
 	pub fn load(path: &str) -> Self {
-		let mut code = String::new();
-		std::fs::read_to_string(path)
-			.unwrap()
-			.chars()
-			.for_each(|c| {
-				code.push(c);
-			});
-		Self::new(&code)
+		Self::load_with_preprocessor(path, Preprocessor::new(), &Options::default())
 	}
 
-	pub fn preprocess(code: &mut String) {
-		const MACROS: [(&str, &str); 1] = [("6", "6")];
-		for (original, expanded) in MACROS.iter() {
-			*code = code.replace(original, expanded);
+	// a program from a file, the macro files of the options are defined before it
+	// and imports are searched in their search path after the directory of the file
+	pub fn load_with(path: &str, options: &Options) -> Self {
+		let mut preprocessor = Preprocessor {
+			search_path: options.search_path.iter().map(PathBuf::from).collect(),
+			..Preprocessor::new()
+		};
+		for file in &options.macro_files {
+			preprocessor.load(file);
 		}
+		Self::load_with_preprocessor(path, preprocessor, options)
+	}
+
+	// imports of the file are relative to its directory
+	pub(crate) fn load_with_preprocessor(
+		path: &str,
+		mut preprocessor: Preprocessor,
+		options: &Options,
+	) -> Self {
+		let source = std::fs::read_to_string(path)
+			.unwrap_or_else(|error| panic!("Cannot read {} : {}", path, error));
+		let path = Path::new(path);
+		let path = path.canonicalize().unwrap_or(path.to_path_buf());
+		preprocessor.directory = path.parent().map(Path::to_path_buf);
+		preprocessor.importing.push(path);
+		Self::with_preprocessor(&source, preprocessor, options)
 	}
 
 	pub fn get_code(&self) -> String {
@@ -92,134 +240,349 @@ impl Program {
 		return code_string;
 	}
 
+	// executes the code of the program from its current instruction
+	// the current instruction is the index of the next token of the code,
+	// which is what lets a program restored from a snapshot resume where it stopped
 	pub fn execute(&mut self) {
-		let tokens = tokenize(&self.get_code());
+		let code = self.get_code();
+		// the code of blocks is checked with the code of their program
+		if self.depth == 0 {
+			check_chars(&code, self.lexing, &self.operators);
+			check_checkpoints(&code);
+		}
+		let tokens = tokenize(&code, self.lexing, &self.operators);
+		for token in tokens.into_iter().skip(self.current_instruction) {
+			if self.control.is_some() {
+				return;
+			}
+			self.execute_token(token);
+			self.current_instruction += 1;
+		}
+	}
+
+	// executes the program, saving a snapshot of it in a file if an instruction panics
+	// the snapshot is the program before the failing instruction, so resuming it runs the instruction again
+	pub fn execute_or_snapshot(&mut self, path: &str) {
+		let code = self.get_code();
+		check_chars(&code, self.lexing, &self.operators);
+		check_checkpoints(&code);
+		let tokens = tokenize(&code, self.lexing, &self.operators);
+		for token in tokens.into_iter().skip(self.current_instruction) {
+			if self.control.is_some() {
+				return;
+			}
+			// only written in the file if the instruction fails
+			let before = self.clone();
+			let result = panic::catch_unwind(AssertUnwindSafe(|| self.execute_token(token)));
+			if let Err(error) = result {
+				before.save_snapshot(path);
+				eprintln!("Snapshot of the program saved in {}", path);
+				panic::resume_unwind(error);
+			}
+			self.current_instruction += 1;
+		}
+	}
+
+	// executes some code directly on this program's stack
+	pub(crate) fn execute_code(&mut self, code: &str) {
+		let tokens = tokenize(code, self.lexing, &self.operators);
+		self.depth += 1;
 		for token in tokens {
-			match token {
-				Token::Integer(x) => self.push(NDArray::SingleValue(SingleValue::Integer(x))),
-				Token::Real(x) => self.push(NDArray::SingleValue(SingleValue::Real(x))),
-				Token::Char(x) => self.push(NDArray::SingleValue(SingleValue::Char(x))),
-				Token::String(x) => {
-					let code = x.chars().collect::<Vec<char>>();
-					self.push(NDArray::from_1d_char(code));
-				}
-				Token::Set(set) => self.push(NDArray::SingleValue(SingleValue::Char(match set {
-					Set::Integer => 'I',
-					Set::Real => 'R',
-					Set::Char => 'C',
-				}))),
-				Token::Conversion => {
-					//take the top of the stack to know what to convert to
-					let set = match self.pop().unwrap() {
-						NDArray::SingleValue(SingleValue::Char(x)) => match x {
-							'I' => Set::Integer,
-							'R' => Set::Real,
-							'C' => Set::Char,
-							_ => panic!("Cannot convert to unknown type"),
-						},
+			if self.control.is_some() {
+				break;
+			}
+			self.execute_token(token);
+		}
+		self.depth -= 1;
+	}
+
+	fn execute_token(&mut self, token: Token) {
+		match token {
+			Token::Integer(x) => self.push(NDArray::SingleValue(SingleValue::Integer(x))),
+			Token::Real(x) => self.push(NDArray::SingleValue(SingleValue::Real(x))),
+			Token::Long(x) => self.push(NDArray::SingleValue(SingleValue::Long(x))),
+			Token::Double(x) => self.push(NDArray::SingleValue(SingleValue::Double(x))),
+			Token::Complex(re, im) => self.push(NDArray::SingleValue(SingleValue::Complex(re, im))),
+			Token::Char(x) => self.push(NDArray::SingleValue(SingleValue::Char(x))),
+			Token::String(x) => {
+				let code = x.chars().collect::<Vec<char>>();
+				self.push(NDArray::from_1d_char(code));
+			}
+			Token::Set(set) => self.push(NDArray::SingleValue(SingleValue::Char(match set {
+				Set::Integer => 'I',
+				Set::Real => 'R',
+				Set::Char => 'C',
+				Set::Long => 'L',
+				Set::Double => 'D',
+				Set::Bool => 'B',
+				Set::Complex => 'ℂ',
+			}))),
+			Token::Conversion => {
+				//take the top of the stack to know what to convert to
+				let set = match self.pop().unwrap() {
+					NDArray::SingleValue(SingleValue::Char(x)) => match x {
+						'I' => Set::Integer,
+						'R' => Set::Real,
+						'C' => Set::Char,
+						'L' => Set::Long,
+						'D' => Set::Double,
+						'B' => Set::Bool,
+						'ℂ' => Set::Complex,
 						_ => panic!("Cannot convert to unknown type"),
-					};
-					//take the next element to know what to convert
-					let to_convert = self.pop().unwrap();
-					// if its a single value
-					if let NDArray::SingleValue(x) = to_convert {
-						let converted = match x {
-							SingleValue::Integer(x) => match set {
-								Set::Integer => SingleValue::Integer(x),
-								Set::Real => SingleValue::Real(x as f32),
-								Set::Char => {
-									SingleValue::Char(unsafe { mem::transmute::<i32, char>(x) })
-								}
-								_ => panic!("Cannot convert to unknown type"),
-							},
-							SingleValue::Real(x) => match set {
-								Set::Integer => SingleValue::Integer(x as i32),
-								Set::Real => SingleValue::Real(x),
-								Set::Char => panic!("Cannot convert real to char"),
-								_ => panic!("Cannot convert to unknown type"),
-							},
-							SingleValue::Char(x) => match set {
-								Set::Integer => {
-									SingleValue::Integer(unsafe { mem::transmute::<char, i32>(x) })
-								}
-								Set::Real => panic!("Cannot convert char to real"),
-								Set::Char => SingleValue::Char(x),
-								_ => panic!("Cannot convert to unknown type"),
-							},
-						};
-						self.push(NDArray::SingleValue(converted));
-					} else {
-						// convert the whole array
-						let mut array_converted = to_convert.clone();
-						let mut indexes = vec![0; array_converted.shape().len()];
-						let final_shape = array_converted.shape().clone();
-						while (indexes
-							.clone()
-							.into_iter()
-							.map(|x| x + 1)
-							.collect::<Vec<_>>() != final_shape)
-						{
-							let mut to_convert = array_converted.get(&indexes);
-							let converted = match to_convert {
-								NDArray::SingleValue(x) => match x {
-									SingleValue::Integer(x) => match set {
-										Set::Integer => SingleValue::Integer(x),
-										Set::Real => SingleValue::Real(x as f32),
-										Set::Char => SingleValue::Char(unsafe {
-											mem::transmute::<i32, char>(x)
-										}),
-										_ => panic!("Cannot convert to unknown type"),
-									},
-									SingleValue::Real(x) => match set {
-										Set::Integer => SingleValue::Integer(x as i32),
-										Set::Real => SingleValue::Real(x),
-										Set::Char => panic!("Cannot convert real to char"),
-										_ => panic!("Cannot convert to unknown type"),
-									},
-									SingleValue::Char(x) => match set {
-										Set::Integer => SingleValue::Integer(unsafe {
-											mem::transmute::<char, i32>(x)
-										}),
-										Set::Real => panic!("Cannot convert char to real"),
-										Set::Char => SingleValue::Char(x),
-										_ => panic!("Cannot convert to unknown type"),
-									},
-								},
-								_ => panic!("Cannot convert array to scalar"),
-							};
-							array_converted.set(&indexes, NDArray::SingleValue(converted));
-
-							let mut index_to_increment = indexes.len() - 1;
-							indexes[index_to_increment] += 1;
-							while indexes[index_to_increment]
-								== array_converted.shape()[index_to_increment]
-							{
-								indexes[index_to_increment] = 0;
-								index_to_increment -= 1;
-								indexes[index_to_increment] += 1;
-							}
-						}
-						self.push(array_converted);
+					},
+					_ => panic!("Cannot convert to unknown type"),
+				};
+				//take the next element to know what to convert
+				//and convert it, element-wise if it's an array
+				let to_convert = self.pop().unwrap();
+				self.push(to_convert.map(&|x| convert(x, &set)));
+			}
+			Token::Operator(name, operator) => {
+				let nb_values = self.stack.len() - 1;
+				if nb_values < operator.arity {
+					panic!(
+						"Operator {} takes {} values but the stack has {}",
+						name, operator.arity, nb_values
+					);
+				}
+				(operator.handler)(self)
+			}
+			Token::Word(name) => {
+				let name = self.resolve(&name, |name| {
+					self.words.contains_key(name) || self.macros.contains_key(name)
+				});
+				if !self.words.contains_key(&name) && !self.macros.contains_key(&name) {
+					// single letter operators and sets written together, like vv or vN
+					match glued_operators(&name, &self.operators) {
+						Some(tokens) => tokens.into_iter().for_each(|x| self.execute_token(x)),
+						None => panic!("Unknown word {}", name),
 					}
+					return;
+				}
+				// the word is executed in its own namespace
+				let namespace = match name.rsplit_once('.') {
+					Some((namespace, _)) => namespace.to_string(),
+					None => String::new(),
+				};
+				self.namespaces.push(namespace);
+				if let Some(code) = self.words.get(&name) {
+					let code = code.clone();
+					self.execute_code(&code);
+				} else if let Some(generator) = self.macros.get(&name) {
+					// the macro's code runs on a copy of the stack
+					// and the char array it results in is the code to execute
+					let mut macro_program = Program::subprogram(&generator.clone(), self);
+					macro_program.execute();
+					let code = macro_program.get_result().get_string();
+					self.execute_code(&code);
 				}
-				Token::Operator(f) => f(self),
-				Token::RealTimeMacro(f) => {
-					// create another program made of the same stack
-					// but with the code being only the macro
-					// then execute that program
-					let mut program = Program::new(&f(self));
-					program.execute();
-					// get the result of the program
-					let result = program.stack.pop().unwrap();
-					// push the result onto the current program's stack
-					self.push(result);
+				self.namespaces.pop();
+			}
+			Token::Store(name) => {
+				let name = self.resolve(&name, |name| self.variables.contains_key(name));
+				let value = self.pop().unwrap();
+				self.variables.insert(name, value);
+			}
+			Token::Load(name) => {
+				let name = self.resolve(&name, |name| self.variables.contains_key(name));
+				let value = match self.variables.get(&name) {
+					Some(value) => value.clone(),
+					None => panic!("Unknown variable {}", name),
+				};
+				self.push(value);
+			}
+			Token::RealTimeMacro(f) => {
+				// expand the macro and execute the code on the current stack
+				let code = f(self);
+				self.execute_code(&code);
+			}
+		}
+	}
+
+	// executes the body of a loop once, returns the control that ended it
+	pub(crate) fn execute_iteration(&mut self, code: &str) -> Option<Control> {
+		self.loops += 1;
+		self.execute_code(code);
+		self.loops -= 1;
+		self.control.take()
+	}
+
+	// the name of a definition in the current namespace if there's one, else the name itself
+	fn resolve(&self, name: &str, is_defined: impl Fn(&str) -> bool) -> String {
+		if let Some(namespace) = self.namespaces.last() {
+			if !namespace.is_empty() {
+				let qualified = format!("{}.{}", namespace, name);
+				if is_defined(&qualified) {
+					return qualified;
 				}
 			}
 		}
+		name.to_string()
 	}
 
+	// the value at the top of the stack
 	pub fn get_result(&self) -> NDArray {
-		let len = self.stack.len();
-		self.stack[len - 1].clone()
+		match self.values().last() {
+			Some(value) => value.clone(),
+			None => panic!("The program has no result, its stack is empty"),
+		}
+	}
+
+	// adds a native operator, or replaces the one with the same name
+	// names starting with a letter or _ are used like words and are made of letters, digits and _,
+	// other ones are glyphs without letters, and none of them can have the chars the lexer reserves
+	pub fn register_operator(&mut self, name: &str, arity: usize, handler: fn(&mut Program)) {
+		let first = name
+			.chars()
+			.next()
+			.unwrap_or_else(|| panic!("Operators need a name"));
+		if first.is_ascii_digit() {
+			panic!("Operator names cannot start with {:?}", first);
+		}
+		if let Some(c) = name
+			.chars()
+			.find(|c| WHITESPACE.contains(c) || RESERVED.contains(c))
+		{
+			panic!("Operator names cannot contain {:?}", c);
+		}
+		if name.contains("{-") {
+			panic!("Operator names cannot contain {{-, which starts a comment");
+		}
+		if SETS.iter().any(|(set, _)| *set == name) {
+			panic!("Operator name {} is the one of a set", name);
+		}
+		let is_word = first.is_alphabetic() || first == '_';
+		let mixed = if is_word {
+			!name.chars().all(|c| c.is_alphanumeric() || c == '_')
+		} else {
+			name.chars().any(|c| c.is_alphabetic() || c == '_')
+		};
+		if mixed {
+			panic!("Operator name {} mixes letters with other characters", name);
+		}
+		Rc::make_mut(&mut self.operators).insert(name, Operator { arity, handler });
+	}
+
+	// removes a native operator, built in or not, returns whether there was one
+	pub fn remove_operator(&mut self, name: &str) -> bool {
+		Rc::make_mut(&mut self.operators).remove(name)
+	}
+
+	// a program whose preprocessing errors are returned instead of panicking
+	pub fn try_new(source: &str) -> Result<Self, Error> {
+		error::catch(|| Self::new(source))
+	}
+
+	// executes the program, its error is returned instead of panicking
+	// the stack and the current instruction are left as they were when the error happened,
+	// the blocks, loops and imported words that were running are left
+	pub fn run(&mut self) -> Result<(), Error> {
+		let result = error::catch(|| self.execute());
+		if result.is_err() {
+			self.depth = 0;
+			self.loops = 0;
+			self.control = None;
+			self.namespaces.clear();
+			self.indices_current.truncate(1);
+		}
+		result
+	}
+
+	// the values of the stack from the bottom, without the code of the program
+	pub fn values(&self) -> &[NDArray] {
+		&self.stack[1..]
+	}
+
+	// index of the next instruction of the code, the failing one after an error
+	pub fn current_instruction(&self) -> usize {
+		self.current_instruction
+	}
+
+	// checked, wrapping or saturating, like the overflow operator
+	pub fn set_overflow(&mut self, mode: &str) {
+		self.overflow = Overflow::from_name(mode);
+	}
+
+	// number of digits print shows after the point of reals, all of them with None
+	pub fn set_precision(&mut self, digits: Option<usize>) {
+		self.format.precision = digits;
+	}
+
+	// the value on top of the stack, as print shows it
+	pub fn format_result(&self) -> String {
+		self.get_result().format(&self.format)
+	}
+}
+
+// Prefixes the names of imported definitions with their namespace
+fn qualify<T>(namespace: &str, definitions: HashMap<String, T>) -> HashMap<String, T> {
+	definitions
+		.into_iter()
+		.map(|(name, x)| (format!("{}.{}", namespace, name), x))
+		.collect()
+}
+
+// Converts a single value to the type of the given set
+fn convert(x: SingleValue, set: &Set) -> SingleValue {
+	match (x, set) {
+		(SingleValue::Integer(x), Set::Integer) => SingleValue::Integer(x),
+		(SingleValue::Integer(x), Set::Real) => SingleValue::Real(x as f32),
+		(SingleValue::Integer(x), Set::Long) => SingleValue::Long(x as i64),
+		(SingleValue::Integer(x), Set::Double) => SingleValue::Double(x as f64),
+		(SingleValue::Integer(x), Set::Char) => SingleValue::Char(
+			char::from_u32(x as u32).unwrap_or_else(|| panic!("{} is not a valid char", x)),
+		),
+		(SingleValue::Long(x), Set::Integer) => SingleValue::Integer(
+			i32::try_from(x)
+				.unwrap_or_else(|_| panic!("Integer overflow in converting {} to N", x)),
+		),
+		(SingleValue::Long(x), Set::Real) => SingleValue::Real(x as f32),
+		(SingleValue::Long(x), Set::Long) => SingleValue::Long(x),
+		(SingleValue::Long(x), Set::Double) => SingleValue::Double(x as f64),
+		(SingleValue::Long(x), Set::Char) => SingleValue::Char(
+			u32::try_from(x)
+				.ok()
+				.and_then(char::from_u32)
+				.unwrap_or_else(|| panic!("{} is not a valid char", x)),
+		),
+		(SingleValue::Real(x), Set::Integer) => SingleValue::Integer(x as i32),
+		(SingleValue::Real(x), Set::Real) => SingleValue::Real(x),
+		(SingleValue::Real(x), Set::Long) => SingleValue::Long(x as i64),
+		(SingleValue::Real(x), Set::Double) => SingleValue::Double(x as f64),
+		(SingleValue::Real(_), Set::Char) => panic!("Cannot convert real to char"),
+		(SingleValue::Double(x), Set::Integer) => SingleValue::Integer(x as i32),
+		(SingleValue::Double(x), Set::Real) => SingleValue::Real(x as f32),
+		(SingleValue::Double(x), Set::Long) => SingleValue::Long(x as i64),
+		(SingleValue::Double(x), Set::Double) => SingleValue::Double(x),
+		(SingleValue::Double(_), Set::Char) => panic!("Cannot convert real to char"),
+		(SingleValue::Char(x), Set::Integer) => SingleValue::Integer(x as i32),
+		(SingleValue::Char(x), Set::Long) => SingleValue::Long(x as i64),
+		(SingleValue::Char(x), Set::Char) => SingleValue::Char(x),
+		(SingleValue::Char(_), Set::Real | Set::Double) => panic!("Cannot convert char to real"),
+		(SingleValue::Integer(x), Set::Bool) => SingleValue::Bool(x != 0),
+		(SingleValue::Long(x), Set::Bool) => SingleValue::Bool(x != 0),
+		(SingleValue::Bool(x), Set::Integer) => SingleValue::Integer(x as i32),
+		(SingleValue::Bool(x), Set::Long) => SingleValue::Long(x as i64),
+		(SingleValue::Bool(x), Set::Bool) => SingleValue::Bool(x),
+		(SingleValue::Real(_) | SingleValue::Double(_), Set::Bool) => {
+			panic!("Cannot convert real to bool")
+		}
+		(SingleValue::Char(_), Set::Bool) => panic!("Cannot convert char to bool"),
+		(SingleValue::Bool(_), Set::Real | Set::Double) => panic!("Cannot convert bool to real"),
+		(SingleValue::Bool(_), Set::Char) => panic!("Cannot convert bool to char"),
+		// converting a complex to a real type keeps the real part
+		(SingleValue::Complex(re, _), Set::Integer) => SingleValue::Integer(re as i32),
+		(SingleValue::Complex(re, _), Set::Real) => SingleValue::Real(re as f32),
+		(SingleValue::Complex(re, _), Set::Long) => SingleValue::Long(re as i64),
+		(SingleValue::Complex(re, _), Set::Double) => SingleValue::Double(re),
+		(SingleValue::Complex(re, im), Set::Complex) => SingleValue::Complex(re, im),
+		(SingleValue::Complex(..), Set::Char) => panic!("Cannot convert complex to char"),
+		(SingleValue::Complex(..), Set::Bool) => panic!("Cannot convert complex to bool"),
+		(SingleValue::Char(_), Set::Complex) => panic!("Cannot convert char to complex"),
+		(SingleValue::Bool(_), Set::Complex) => panic!("Cannot convert bool to complex"),
+		(x, Set::Complex) => {
+			let (re, im) = x.get_complex();
+			SingleValue::Complex(re, im)
+		}
 	}
 }
@@ -0,0 +1,358 @@
+// Snapshots of a running program, to save it in a file and resume it later
+// a snapshot is a text file with one line per part of the program :
+// chimera-snapshot 1
+// instruction 3
+// indices 2
+// overflow checked
+// lexing lenient
+// format none 1000 3
+// word name "code"
+// macro name "code"
+// variable name value
+// stack value
+// with the elements of the stack from the bottom, the code of the program being the first one
+// values are written as a letter for their type followed by their contents,
+// i l r d b c z for single values and a for arrays with their shape and their elements
+// native operators aren't saved, a resumed program has the built-in ones
+
+use std::collections::HashMap;
+use std::str::SplitWhitespace;
+
+use crate::ndarray::{Format, NDArray, Overflow, SingleValue};
+use crate::preprocessor::Preprocessor;
+use crate::program::{Options, Program};
+use crate::stack::Stack;
+use crate::token::Lexing;
+
+const HEADER: &str = "chimera-snapshot 1";
+
+impl Program {
+	pub fn save_snapshot(&self, path: &str) {
+		std::fs::write(path, self.snapshot())
+			.unwrap_or_else(|error| panic!("Cannot write {} : {}", path, error));
+	}
+
+	pub fn load_snapshot(path: &str) -> Self {
+		let snapshot = std::fs::read_to_string(path)
+			.unwrap_or_else(|error| panic!("Cannot read {} : {}", path, error));
+		Self::from_snapshot(&snapshot)
+	}
+
+	// only a program between two instructions of its code can be resumed from its snapshot
+	pub fn snapshot(&self) -> String {
+		if self.depth != 0 {
+			panic!(
+				"Cannot take a snapshot inside a block, only between instructions of the program"
+			);
+		}
+		let mut lines = vec![HEADER.to_string()];
+		lines.push(format!("instruction {}", self.current_instruction));
+		let indices = self.indices_current.iter().map(|x| x.to_string());
+		lines.push(format!("indices {}", indices.collect::<Vec<_>>().join(" ")));
+		lines.push(format!("overflow {}", self.overflow.name()));
+		lines.push(format!(
+			"lexing {}",
+			match self.lexing {
+				Lexing::Strict => "strict",
+				Lexing::Lenient => "lenient",
+			}
+		));
+		let precision = match self.format.precision {
+			Some(precision) => precision.to_string(),
+			None => String::from("none"),
+		};
+		lines.push(format!(
+			"format {} {} {}",
+			precision, self.format.threshold, self.format.edge_items
+		));
+		for (name, code) in sorted(&self.words) {
+			lines.push(format!("word {} {}", name, write_code(code)));
+		}
+		for (name, code) in sorted(&self.macros) {
+			lines.push(format!("macro {} {}", name, write_code(code)));
+		}
+		for (name, value) in sorted(&self.variables) {
+			lines.push(format!("variable {} {}", name, write_value(value)));
+		}
+		for value in self.stack.iter() {
+			lines.push(format!("stack {}", write_value(value)));
+		}
+		lines.join("\n") + "\n"
+	}
+
+	pub fn from_snapshot(snapshot: &str) -> Self {
+		let mut lines = snapshot.lines();
+		if lines.next() != Some(HEADER) {
+			panic!("Not a snapshot of a Chimera program");
+		}
+		let options = Options {
+			no_prelude: true,
+			..Options::default()
+		};
+		let mut program = Program::with_preprocessor("", Preprocessor::new(), &options);
+		program.stack = Stack::new();
+		for line in lines.filter(|x| !x.is_empty()) {
+			let (key, rest) = line.split_once(' ').unwrap_or((line, ""));
+			match key {
+				"instruction" => program.current_instruction = parse(rest),
+				"indices" => program.indices_current = rest.split_whitespace().map(parse).collect(),
+				"overflow" => program.overflow = Overflow::from_name(rest),
+				"lexing" => {
+					program.lexing = match rest {
+						"strict" => Lexing::Strict,
+						"lenient" => Lexing::Lenient,
+						_ => panic!("Unknown lexing {} in snapshot", rest),
+					}
+				}
+				"format" => {
+					let mut parts = rest.split_whitespace();
+					program.format = Format {
+						precision: match parts.next() {
+							Some("none") => None,
+							x => Some(parse(x.unwrap_or(""))),
+						},
+						threshold: parse(parts.next().unwrap_or("")),
+						edge_items: parse(parts.next().unwrap_or("")),
+					}
+				}
+				"word" => {
+					let (name, code) = split_name(rest);
+					program.words.insert(name, read_code(code));
+				}
+				"macro" => {
+					let (name, code) = split_name(rest);
+					program.macros.insert(name, read_code(code));
+				}
+				"variable" => {
+					let (name, value) = split_name(rest);
+					program.variables.insert(name, read_value(value));
+				}
+				"stack" => program.stack.push(read_value(rest)),
+				_ => panic!("Unknown line {} in snapshot", line),
+			}
+		}
+		program
+	}
+}
+
+fn split_name(text: &str) -> (String, &str) {
+	let (name, rest) = text
+		.split_once(' ')
+		.unwrap_or_else(|| panic!("Expected a name and a value in snapshot"));
+	(name.to_string(), rest)
+}
+
+fn sorted<T>(map: &HashMap<String, T>) -> Vec<(&String, &T)> {
+	let mut entries = map.iter().collect::<Vec<_>>();
+	entries.sort_by_key(|(name, _)| *name);
+	entries
+}
+
+fn parse<T: std::str::FromStr>(text: &str) -> T {
+	text.parse()
+		.unwrap_or_else(|_| panic!("Invalid number {} in snapshot", text))
+}
+
+// code is written as a JSON string so that it fits on a line
+fn write_code(code: &str) -> String {
+	NDArray::from_1d_char(code.chars().collect()).to_json()
+}
+
+fn read_code(text: &str) -> String {
+	NDArray::from_json(text).get_string()
+}
+
+fn write_value(value: &NDArray) -> String {
+	let mut text = String::new();
+	write_value_into(value, &mut text);
+	text
+}
+
+// floats are written in their shortest form, which reads back as the same float
+fn write_value_into(value: &NDArray, text: &mut String) {
+	let part = match value {
+		NDArray::SingleValue(x) => match x {
+			SingleValue::Integer(x) => format!("i {}", x),
+			SingleValue::Long(x) => format!("l {}", x),
+			SingleValue::Real(x) => format!("r {}", x),
+			SingleValue::Double(x) => format!("d {}", x),
+			SingleValue::Bool(x) => format!("b {}", x),
+			SingleValue::Char(x) => format!("c {}", *x as u32),
+			SingleValue::Complex(re, im) => format!("z {} {}", re, im),
+		},
+		NDArray::NDArray { shape, inner } => {
+			let shape = shape.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+			text.push_str(&format!(
+				"a {} {} {}",
+				shape.len(),
+				shape.join(" "),
+				inner.len()
+			));
+			for x in inner {
+				text.push(' ');
+				write_value_into(x, text);
+			}
+			return;
+		}
+	};
+	text.push_str(&part);
+}
+
+fn read_value(text: &str) -> NDArray {
+	let mut parts = text.split_whitespace();
+	let value = read_value_from(&mut parts);
+	if parts.next().is_some() {
+		panic!("Unexpected text after the value {} in snapshot", text);
+	}
+	value
+}
+
+fn read_value_from(parts: &mut SplitWhitespace) -> NDArray {
+	let kind = next_part(parts);
+	NDArray::SingleValue(match kind {
+		"i" => SingleValue::Integer(parse(next_part(parts))),
+		"l" => SingleValue::Long(parse(next_part(parts))),
+		"r" => SingleValue::Real(parse(next_part(parts))),
+		"d" => SingleValue::Double(parse(next_part(parts))),
+		"b" => SingleValue::Bool(parse(next_part(parts))),
+		"c" => SingleValue::Char(
+			char::from_u32(parse(next_part(parts)))
+				.unwrap_or_else(|| panic!("Invalid char in snapshot")),
+		),
+		"z" => SingleValue::Complex(parse(next_part(parts)), parse(next_part(parts))),
+		"a" => {
+			let nb_dims = parse::<usize>(next_part(parts));
+			let shape = (0..nb_dims).map(|_| parse(next_part(parts))).collect();
+			let nb_elements = parse::<usize>(next_part(parts));
+			let inner = (0..nb_elements).map(|_| read_value_from(parts)).collect();
+			return NDArray::NDArray { shape, inner };
+		}
+		_ => panic!("Unknown value {} in snapshot", kind),
+	})
+}
+
+fn next_part<'a>(parts: &mut SplitWhitespace<'a>) -> &'a str {
+	parts
+		.next()
+		.unwrap_or_else(|| panic!("Incomplete value in snapshot"))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::error;
+	use crate::ndarray::tests::typed;
+	use crate::ndarray::{NDArray, SingleValue};
+	use crate::program::Program;
+
+	fn path(name: &str) -> String {
+		let path = std::env::temp_dir().join(format!("chimera_{}_{}", name, std::process::id()));
+		path.to_str().unwrap().to_string()
+	}
+
+	// the stack without the code, with the types of the values
+	fn values(program: &Program) -> Vec<String> {
+		program.stack.iter().skip(1).map(typed).collect()
+	}
+
+	fn integer(x: i32) -> NDArray {
+		NDArray::SingleValue(SingleValue::Integer(x))
+	}
+
+	#[test]
+	fn round_trips() {
+		let source =
+			"5 →x 1.5 ←x 2 1 ^ →y (1 +) \"inc\" ≝ 'c' 3000000000 3.14159265 \"a\\nb\" 2.5j 2 3 1 ⊹";
+		let mut program = Program::new(source);
+		program.execute();
+		let snapshot = program.snapshot();
+		let restored = Program::from_snapshot(&snapshot);
+		assert_eq!(restored.snapshot(), snapshot);
+		assert_eq!(values(&restored), values(&program));
+		assert_eq!(restored.current_instruction, program.current_instruction);
+		assert_eq!(restored.indices_current, program.indices_current);
+		assert_eq!(restored.words, program.words);
+	}
+
+	#[test]
+	fn checkpoint() {
+		let file = path("checkpoint");
+		let mut program = Program::new(&format!("1 2 + \"{}\" checkpoint 10 ×", file));
+		program.execute();
+		let mut resumed = Program::load_snapshot(&file);
+		assert_eq!(values(&resumed), vec!["Integer(3)"]);
+		resumed.execute();
+		assert_eq!(values(&resumed), values(&program));
+		assert_eq!(values(&resumed), vec!["Integer(30)"]);
+		std::fs::remove_file(file).unwrap();
+	}
+
+	// the snapshot of an error is taken before the failing instruction
+	#[test]
+	fn snapshot_on_error() {
+		let file = path("snapshot_on_error");
+		let mut program = Program::new("7 1 2 + 0 3 / 5 +");
+		assert!(error::catch(|| program.execute_or_snapshot(&file)).is_err());
+		let mut resumed = Program::load_snapshot(&file);
+		assert_eq!(resumed.current_instruction, 6);
+		assert_eq!(
+			values(&resumed),
+			vec!["Integer(7)", "Integer(3)", "Integer(0)", "Integer(3)"]
+		);
+		// fixing the divisor and resuming runs the division again
+		resumed.pop();
+		resumed.pop();
+		resumed.push(integer(1));
+		resumed.push(integer(3));
+		resumed.execute();
+		assert_eq!(
+			values(&resumed),
+			vec!["Integer(7)", "Integer(3)", "Integer(8)"]
+		);
+		std::fs::remove_file(file).unwrap();
+	}
+
+	#[test]
+	fn inside_blocks() {
+		let mut program = Program::new("");
+		program.depth = 1;
+		assert!(error::catch(|| program.snapshot()).is_err());
+		// checkpoints in blocks are found before anything runs
+		let file = path("inside_blocks");
+		let source = format!("1 \"x\" print\n0 3 (1 + \"{}\" checkpoint) times", file);
+		let message = "Cannot use checkpoint inside a block, at line 2, \
+			only between instructions of the program";
+		let mut program = Program::new(&source);
+		assert_eq!(
+			error::catch(|| program.execute()).unwrap_err().message,
+			message
+		);
+		assert_eq!(values(&program), Vec::<String>::new());
+		let mut program = Program::new(&source);
+		let error = error::catch(|| program.execute_or_snapshot(&file)).unwrap_err();
+		assert_eq!(error.message, message);
+		assert!(!std::path::Path::new(&file).exists());
+		// but not in strings and comments
+		let mut program = Program::new("\"(checkpoint)\" (1 ⍝ checkpoint\n)");
+		program.execute();
+		assert_eq!(values(&program).len(), 2);
+	}
+
+	#[test]
+	fn invalid() {
+		for snapshot in [
+			"",
+			"chimera-snapshot 2\n",
+			"chimera-snapshot 1\nstack i\n",
+			"chimera-snapshot 1\nstack q 1\n",
+			"chimera-snapshot 1\nstack i 1 2\n",
+			"chimera-snapshot 1\nlexing loose\n",
+			"chimera-snapshot 1\nunknown 1\n",
+		] {
+			assert!(
+				error::catch(|| Program::from_snapshot(snapshot)).is_err(),
+				"{:?}",
+				snapshot
+			);
+		}
+	}
+}
@@ -3,6 +3,7 @@ use std::ops::Deref;
 
 use crate::ndarray::NDArray;
 
+#[derive(Clone)]
 pub struct Stack {
 	pub inner: Vec<NDArray>,
 }
@@ -34,10 +35,6 @@ impl Stack {
 	pub fn peek(&self) -> Option<&NDArray> {
 		self.inner.last()
 	}
-
-	pub fn from_vec(inner: Vec<NDArray>) -> Self {
-		Self { inner }
-	}
 }
 
 impl Deref for Stack {
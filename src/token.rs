@@ -3,255 +3,221 @@
 // N = Integer
 // R = Real
 // C = Char
+// L = Long (64 bits integer)
+// D = Double (64 bits real)
+// B = Bool
+// ℂ = Complex
 // P = Program
 
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read, Write};
+use std::iter::Peekable;
 use std::mem;
+use std::str::Chars;
 
-use crate::ndarray::{NDArray, SingleValue};
-use crate::program::{self, Program};
+use crate::ndarray::{NDArray, Overflow, SingleValue};
+use crate::preprocessor;
+use crate::program::{self, Control, Program};
 use crate::stack::Stack;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum Set {
 	Integer,
 	Real,
 	Char,
+	Long,
+	Double,
+	Bool,
+	Complex,
 }
 
 #[derive(Debug)]
 pub enum Token {
 	Integer(i32),
 	Real(f32),
+	Long(i64),
+	Double(f64),
+	// real and imaginary parts
+	Complex(f64, f64),
 	Char(char),
 	String(String),
 	Set(Set),
 	Conversion,
-	// Real time macros expand to other code that'll get executed in another program
+	// Real time macros look at the program to expand to other code that then gets executed
 	RealTimeMacro(fn(&Program) -> String),
-	Operator(fn(&mut Program)),
+	// a native operator with its name
+	Operator(String, Operator),
+	// A word defined with ≝ or a real time macro defined with ≜,
+	// executed on the stack of the program calling it
+	Word(String),
+	// pops the top of the stack into a variable
+	Store(String),
+	// pushes the value of a variable
+	Load(String),
 }
 
-pub fn tokenize(source: &str) -> Vec<Token> {
-	let mut tokens = Vec::new();
-	let mut chars = source.chars().peekable();
-	while let Some(c) = chars.next() {
-		match c {
-			'v' => {
-				//pop
-				tokens.push(Token::Operator(|program| {
-					program.pop();
-				}));
-			}
-			'/' => {
-				//divide
-				tokens.push(Token::Operator(|program| {
-					let a = program.pop().unwrap();
-					let b = program.pop().unwrap();
-					let a = a.get_single_value();
-					let b = b.get_single_value();
-					program.push(NDArray::division(a, b));
-				}));
-			}
-			'§' => {
-				tokens.push(Token::Conversion);
-			}
-			'&' => {
-				//get current element
-				tokens.push(Token::Operator(|program| {
-					//dbg!(&program.indices_current);
-					//dbg!(&program.stack);
-					//get the current element
-					let indices = &program.indices_current;
-					let mut current = program.stack[indices[0]].clone();
-					for i in indices.iter().skip(1) {
-						current = current.get(&[*i]);
-					}
-					program.push(current);
-				}));
-			}
-			'd' => {
-				//debug
-				tokens.push(Token::Operator(|program| {
-					println!("DEBUG");
-					dbg!(&program);
-				}));
-			}
-			'~' => {
-				//swap the two top elements
-				tokens.push(Token::Operator(|program| {
-					let a = program.pop().unwrap();
-					let b = program.pop().unwrap();
-					program.push(a);
-					program.push(b);
-				}));
-			}
-			'=' => {
-				//double equal
-				let next_char = chars.peek();
-				if let Some('=') = next_char {
-					chars.next();
-					tokens.push(Token::Operator(|program| {
-						let a = program.pop().unwrap();
-						let b = program.pop().unwrap();
-						let a = a.get_single_value();
-						let b = b.get_single_value();
-						program.push(NDArray::SingleValue(SingleValue::Integer(if a == b {
-							1
-						} else {
-							0
-						})));
-					}));
-				}
-				// map operator
-				else {
-					tokens.push(Token::Operator(|program| {
-						let code = program.pop().unwrap();
-						let array = program.pop().unwrap();
-
-						//print the code
-						let mut program_string = String::new();
-						if let NDArray::NDArray { shape, inner } = &code {
-							for x in inner {
-								match x {
-									NDArray::SingleValue(SingleValue::Char(x)) => {
-										program_string.push(*x);
-									}
-									_ => panic!("Expected a program"),
-								}
-							}
-						} else {
-							panic!("Expected a program");
-						}
+// Names lexed as sets
+pub const SETS: [(&str, Set); 6] = [
+	("N", Set::Integer),
+	("R", Set::Real),
+	("C", Set::Char),
+	("L", Set::Long),
+	("D", Set::Double),
+	("B", Set::Bool),
+];
 
-						let (array_shape, mut array_inner) =
-							if let NDArray::NDArray { shape, inner } = array {
-								(shape, inner)
-							} else {
-								dbg!(array);
-								panic!("Expected an array");
-							};
-
-						// for each element in the array
-						// execute the code
-
-						for i in 0..array_shape[0] {
-							program.indices_current.push(i);
-							let mut other_program = Program::subprogram(&program_string, program);
-							//dbg!(&other_program);
-							other_program.execute();
-							let result_element = other_program.get_result();
-							// set the result in the new array
-							array_inner[i] = result_element;
-							program.indices_current.pop();
-						}
+// Operators every program starts with : name, arity and handler
+// the names are glyphs or names like the ones of words
+type BuiltinOperator = (&'static str, usize, fn(&mut Program));
 
-						program.push(NDArray::NDArray {
-							shape: array_shape.clone(),
-							inner: array_inner,
-						});
-					}));
-				}
-			}
-			// reshape operator
-			'^' => {
-				tokens.push(Token::Operator(|program| {
-					let nb_dims = program.pop().unwrap().get_integer();
-					let mut shape = Vec::new();
-					for _ in 0..nb_dims {
-						let dim = program.pop().unwrap().get_integer();
-						shape.push(dim as usize);
-					}
-					let mut values = Vec::new();
-					for _ in 0..shape.iter().product() {
-						values.push(program.pop().unwrap());
-					}
+pub const BUILTIN_OPERATORS: [BuiltinOperator; 63] = [
+	("/", 2, divide),
+	("&", 0, current),
+	("~", 2, swap),
+	("↻", 3, rot),
+	("↺", 3, unrot),
+	("⇈", 2, over),
+	("⇊", 2, nip),
+	("⇅", 2, tuck),
+	("⇑", 1, pick),
+	("⟳", 1, roll),
+	("№", 0, depth),
+	("∅", 0, clear),
+	("<", 2, less),
+	(">", 2, greater),
+	("==", 2, equal),
+	("=", 2, map),
+	("^", 1, reshape),
+	("¨", 4, fold),
+	("@", 1, index),
+	("[]", 1, current_index),
+	("ℜ", 1, real_part),
+	("ℑ", 1, imaginary_part),
+	("‖", 1, magnitude),
+	("∠", 1, phase),
+	("+", 2, add),
+	("-", 2, substract),
+	("×", 2, multiply),
+	("∔", 2, checked_add),
+	("∸", 2, checked_substract),
+	("⊕", 2, wrapping_add),
+	("⊖", 2, wrapping_substract),
+	("⊞", 2, saturating_add),
+	("⊟", 2, saturating_substract),
+	("⨰", 2, checked_multiply),
+	("⊗", 2, wrapping_multiply),
+	("⊠", 2, saturating_multiply),
+	("overflow", 1, set_overflow),
+	("©", 1, copy),
+	("⊹", 1, zeros),
+	("%", 2, modulo),
+	("≝", 2, define_word),
+	("≜", 2, define_macro),
+	("v", 1, drop),
+	("d", 0, debug),
+	("ifelse", 3, ifelse),
+	("while", 2, while_loop),
+	("until", 2, until),
+	("times", 2, times),
+	("each", 2, each),
+	("break", 0, break_loop),
+	("continue", 0, continue_loop),
+	("print", 1, print),
+	("write", 1, write),
+	("newline", 0, newline),
+	("readline", 0, read_line),
+	("readall", 0, read_all),
+	("tojson", 1, to_json),
+	("fromjson", 1, from_json),
+	("loadnpy", 1, load_npy),
+	("savenpy", 2, save_npy),
+	("loadcsv", 2, load_csv),
+	("savecsv", 2, save_csv),
+	("checkpoint", 1, checkpoint),
+];
 
-					let mut array = NDArray::zeros(&shape);
-					let total = shape.iter().product();
-					for i in (0..total).rev() {
-						let mut indices = vec![0; shape.len()];
-						let mut index = i;
-						for j in 0..shape.len() {
-							let dim = shape[j];
-							indices[j] = index % dim;
-							index /= dim;
-						}
-						let indices = indices.iter().rev().map(|x| *x).collect::<Vec<_>>();
-						array.set(&indices, values[total - i - 1].clone());
-					}
-					program.push(array);
-				}));
-			}
-			// fold left operator with window
-			'¨' => {
-				tokens.push(Token::Operator(|program| {
-					let operation = program.pop().unwrap();
-					let window_size = program.pop().unwrap().get_integer();
-					let identity = program.pop().unwrap();
-					let array = program.pop().unwrap();
-
-					let mut array = if let NDArray::NDArray { shape, inner } = array {
-						shape;
-						inner
-					} else {
-						panic!("Expected an array");
-					};
-
-					let mut result = identity;
-					let mut window = Vec::new();
-					for i in 0..array.len() {
-						window.push(array[i].clone());
-						if window.len() == window_size as usize {
-							let mut program_string = String::new();
-							if let NDArray::NDArray { shape, inner } = &operation {
-								for x in inner {
-									match x {
-										NDArray::SingleValue(SingleValue::Char(x)) => {
-											program_string.push(*x);
-										}
-										_ => panic!("Expected a program"),
-									}
-								}
-							} else {
-								panic!("Expected a program");
-							}
-							let mut other_program = Program::subprogram(&program_string, program);
-							other_program.stack.push(result);
-							for x in window.iter().rev() {
-								other_program.stack.push(x.clone());
-							}
-							other_program.execute();
-							result = other_program.get_result();
-							window.remove(0);
-						}
-					}
+// A native operator, built in or registered by the program embedding Chimera
+#[derive(Clone, Copy, Debug)]
+pub struct Operator {
+	// number of values it pops at least, checked before it runs
+	pub arity: usize,
+	pub handler: fn(&mut Program),
+}
 
-					program.push(result);
-				}));
-			}
-			'@' => {
-				tokens.push(Token::Operator(|program| {
-					//dbg!(&program.stack);
-					let indices = program.pop().unwrap();
-					if let NDArray::SingleValue(SingleValue::Integer(x)) = indices {
-						let value = program.stack.get(x as usize).unwrap().clone();
-						program.push(value);
-						return;
-					}
-					//get the current element
-					let mut current =
-						program.stack[indices.get(&[0]).get_integer() as usize].clone();
-					//dbg!(&current);
-					for i in 1..indices.shape()[0] {
-						current = current.get(&[indices.get(&[i]).get_integer() as usize]);
-						//dbg!(&current);
-					}
+// The native operators of a program from their name,
+// with their names indexed by their first char for the lexer to find glyphs
+#[derive(Clone, Debug, Default)]
+pub struct Operators {
+	by_name: HashMap<String, Operator>,
+	by_first_char: HashMap<char, Vec<String>>,
+}
+
+impl Operators {
+	pub fn get(&self, name: &str) -> Option<Operator> {
+		self.by_name.get(name).copied()
+	}
+
+	pub fn insert(&mut self, name: &str, operator: Operator) {
+		if self.by_name.insert(name.to_string(), operator).is_none() {
+			let first = name.chars().next().unwrap();
+			self.by_first_char
+				.entry(first)
+				.or_default()
+				.push(name.to_string());
+		}
+	}
+
+	// whether there was an operator with this name
+	pub fn remove(&mut self, name: &str) -> bool {
+		if self.by_name.remove(name).is_none() {
+			return false;
+		}
+		let first = name.chars().next().unwrap();
+		if let Some(names) = self.by_first_char.get_mut(&first) {
+			names.retain(|x| x != name);
+		}
+		true
+	}
+
+	// names of the operators starting with this char
+	fn starting_with(&self, c: char) -> &[String] {
+		self.by_first_char.get(&c).map_or(&[], Vec::as_slice)
+	}
+}
+
+pub fn builtin_operators() -> Operators {
+	let mut operators = Operators::default();
+	for &(name, arity, handler) in BUILTIN_OPERATORS.iter() {
+		operators.insert(name, Operator { arity, handler });
+	}
+	operators
+}
+
+// How the lexer handles characters that aren't part of the language
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Lexing {
+	// unknown characters are an error
+	Strict,
+	// unknown characters are skipped with a warning
+	#[default]
+	Lenient,
+}
+
+// Characters that only separate tokens
+pub const WHITESPACE: [char; 4] = [' ', '\t', '\n', '\r'];
 
-					//dbg!(&indices);
-					//dbg!(&current);
+// Characters the lexer reads before looking for operators, which operator names can't contain
+pub const RESERVED: [char; 12] = ['(', ')', '"', '\'', '→', '←', '§', '⍝', 'ℂ', '¤', '⋱', '.'];
 
-					program.push(current);
-				}));
+// operators are looked up in the registry of the program, by name for names
+// and by the longest glyph matching the code for the other characters
+pub fn tokenize(source: &str, lexing: Lexing, operators: &Operators) -> Vec<Token> {
+	let mut tokens = Vec::new();
+	let mut chars = source.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			'§' => {
+				tokens.push(Token::Conversion);
 			}
 			'0'..='9' => {
 				let mut number = String::new();
@@ -259,7 +225,17 @@ pub fn tokenize(source: &str) -> Vec<Token> {
 				while let Some('0'..='9') = chars.peek() {
 					number.push(chars.next().unwrap());
 				}
-				tokens.push(Token::Integer(number.parse::<i32>().unwrap()));
+				if let Some('.') = chars.peek() {
+					number.push(chars.next().unwrap());
+					while let Some('0'..='9') = chars.peek() {
+						number.push(chars.next().unwrap());
+					}
+					tokens.push(real_token(&number, &mut chars));
+				} else if let Some('j') = chars.peek() {
+					tokens.push(real_token(&number, &mut chars));
+				} else {
+					tokens.push(integer_token(&number));
+				}
 			}
 			'.' => {
 				let mut number = String::new();
@@ -267,21 +243,51 @@ pub fn tokenize(source: &str) -> Vec<Token> {
 				while let Some('0'..='9') = chars.peek() {
 					number.push(chars.next().unwrap());
 				}
-				tokens.push(Token::Real(number.parse::<f32>().unwrap()));
+				tokens.push(real_token(&number, &mut chars));
 			}
 			'\'' => {
-				let mut character = String::new();
-				character.push(chars.next().unwrap());
-				if chars.next().unwrap() != '\'' {
+				let character = match chars.next() {
+					Some('\\') => read_escape(&mut chars),
+					Some(c) => c,
+					None => panic!("Expected a char after '"),
+				};
+				if chars.next() != Some('\'') {
 					panic!("Expected closing '");
 				}
-				tokens.push(Token::Char(character.chars().next().unwrap()));
+				tokens.push(Token::Char(character));
 			}
 			'(' => {
 				//push everything inbetween the parenthesis as a program
 				let mut program = String::new();
 				let mut nb_parenthesis = 1;
+				// the char before, r" only starts a raw string outside of a name
+				let mut previous = c;
 				while let Some(c) = chars.next() {
+					let after_name = previous.is_alphanumeric() || previous == '_';
+					previous = c;
+					// parenthesis and comments in literals don't count, nor do parenthesis in comments
+					if c == '"' || c == '\'' {
+						program.push(c);
+						copy_quoted(c, &mut chars, &mut program);
+						continue;
+					}
+					if c == 'r' && !after_name && matches!(chars.peek(), Some('"') | Some('#')) {
+						program.push(c);
+						copy_raw_string(&mut chars, &mut program);
+						continue;
+					}
+					if c == '⍝' {
+						program.push(c);
+						program.push_str(&read_line_comment(&mut chars));
+						continue;
+					}
+					if c == '{' && chars.peek() == Some(&'-') {
+						chars.next();
+						program.push_str("{-");
+						program.push_str(&read_block_comment(&mut chars));
+						program.push_str("-}");
+						continue;
+					}
 					if c == '(' {
 						nb_parenthesis += 1;
 					} else if c == ')' {
@@ -298,48 +304,58 @@ pub fn tokenize(source: &str) -> Vec<Token> {
 				//string token
 				let mut string = String::new();
 				//put all the chars in the string until we find a closing "
+				let mut closed = false;
 				while let Some(c) = chars.next() {
-					if c == '"' {
-						break;
+					match c {
+						'"' => {
+							closed = true;
+							break;
+						}
+						'\\' => string.push(read_escape(&mut chars)),
+						_ => string.push(c),
 					}
-					string.push(c);
+				}
+				if !closed {
+					panic!("Expected closing \"");
 				}
 				tokens.push(Token::String(string));
 			}
-			'N' => tokens.push(Token::Set(Set::Integer)),
-			'R' => tokens.push(Token::Set(Set::Real)),
-			'C' => tokens.push(Token::Set(Set::Char)),
-			'+' => tokens.push(Token::Operator(|program| {
-				let a = program.pop().unwrap();
-				let b = program.pop().unwrap();
-				let a = a.get_single_value();
-				let b = b.get_single_value();
-				program.push(NDArray::addition(a, b));
-			})),
-			'-' => tokens.push(Token::Operator(|program| {
-				let a = program.pop().unwrap();
-				let b = program.pop().unwrap();
-				let a = a.get_single_value();
-				let b = b.get_single_value();
-				program.push(NDArray::substraction(a, b));
-			})),
-			//copy operator
-			'©' => tokens.push(Token::Operator(|program| {
-				let a = program.pop().unwrap();
-				program.push(a.clone());
-				program.push(a.clone());
-			})),
-			// create an ndarray
-			'⊹' => tokens.push(Token::Operator(|program| {
-				let nb_dims = program.pop().unwrap().get_integer();
-				let mut shape = Vec::new();
-				for _ in 0..nb_dims {
-					let dim = program.pop().unwrap().get_integer();
-					shape.push(dim as usize);
+			// raw string token : r"..." or r#"..."# with as many # as needed, no escapes
+			'r' if matches!(chars.peek(), Some('"') | Some('#')) => {
+				let mut nb_hashes = 0;
+				while let Some('#') = chars.peek() {
+					chars.next();
+					nb_hashes += 1;
 				}
-				let array = NDArray::zeros(&shape);
-				program.push(array);
-			})),
+				if chars.next() != Some('"') {
+					panic!("Expected \" to open raw string");
+				}
+				let mut string = String::new();
+				let mut closed = false;
+				while let Some(c) = chars.next() {
+					if c == '"' {
+						// only closes if followed by the same number of #
+						let mut hashes = 0;
+						while hashes < nb_hashes && chars.peek() == Some(&'#') {
+							chars.next();
+							hashes += 1;
+						}
+						if hashes == nb_hashes {
+							closed = true;
+							break;
+						}
+						string.push('"');
+						string.push_str(&"#".repeat(hashes));
+					} else {
+						string.push(c);
+					}
+				}
+				if !closed {
+					panic!("Expected closing \" for raw string");
+				}
+				tokens.push(Token::String(string));
+			}
+			'ℂ' => tokens.push(Token::Set(Set::Complex)),
 			// FIXME : c'est juste un test celui la
 			'¤' => tokens.push(Token::RealTimeMacro(|program| {
 				let stack = &program.stack;
@@ -351,99 +367,1465 @@ pub fn tokenize(source: &str) -> Vec<Token> {
 			'⋱' => tokens.push(Token::RealTimeMacro(|program| {
 				return String::from("©2⊹=1[]2[]");
 			})),
-			'[' => {
-				let next_char = chars.peek().unwrap();
-				if *next_char == ']' {
-					tokens.push(Token::Operator(|program| {
-						let i = program.pop().unwrap();
-						let i = i.get_integer() as usize;
-						let result = program.indices_current[i];
-						let result = NDArray::SingleValue(SingleValue::Integer(result as i32));
-						program.push(result);
-					}));
+			// store into a variable : →name
+			'→' => tokens.push(Token::Store(read_variable_name(&mut chars))),
+			// load a variable : ←name
+			'←' => tokens.push(Token::Load(read_variable_name(&mut chars))),
+			// keyword, set or name of a word
+			c if c.is_alphabetic() || c == '_' => {
+				let mut name = String::new();
+				name.push(c);
+				read_name(&mut chars, &mut name);
+				if let Some((_, set)) = SETS.iter().find(|(x, _)| *x == name) {
+					tokens.push(Token::Set(*set));
+				} else if let Some(operator) = operators.get(&name) {
+					tokens.push(Token::Operator(name, operator));
+				} else {
+					tokens.push(Token::Word(name));
 				}
 			}
-			'i' => {
-				//ifelse token
-				//take the 5 next chars
-				let mut ifelse = String::new();
-				for _ in 0..5 {
-					ifelse.push(chars.next().unwrap());
-				}
-				if ifelse == "felse" {
-					tokens.push(Token::Operator(|program| {
-						let condition = program.pop().unwrap();
-						let condition = condition.get_integer();
-						let if_code = program.pop().unwrap();
-						let else_code = program.pop().unwrap();
-
-						let bloc_to_execute = if condition == 0 { else_code } else { if_code };
-						//turn the bloc into a program
-						let mut program_string = String::new();
-						if let NDArray::NDArray { shape, inner } = bloc_to_execute {
-							for x in inner {
-								program_string.push(x.get_char());
-							}
-						} else {
-							program_string.push(bloc_to_execute.get_char());
-						}
-						let mut other_program = Program::subprogram(&program_string, program);
-						other_program.execute();
-						let result = other_program.pop().unwrap();
-						program.push(result);
-					}));
+			// line comment : ⍝ until the end of the line
+			'⍝' => {
+				read_line_comment(&mut chars);
+			}
+			// block comment : {- ... -}, they can be nested
+			'{' if chars.peek() == Some(&'-') => {
+				chars.next();
+				read_block_comment(&mut chars);
+			}
+			c if WHITESPACE.contains(&c) => {}
+			c => match read_glyph(c, &mut chars, operators) {
+				Some(glyph) => {
+					let operator = operators.get(&glyph).unwrap();
+					tokens.push(Token::Operator(glyph, operator));
 				}
+				// the code of the program was checked before it ran,
+				// only code made while it runs can have unknown characters here
+				None if lexing == Lexing::Strict => panic!("Unknown character '{}'", c),
+				None => {}
+			},
+		}
+	}
+	tokens
+}
+
+// Copies a string or a char literal of a block up to its closing quote, escapes included
+fn copy_quoted(quote: char, chars: &mut Peekable<Chars>, block: &mut String) {
+	while let Some(c) = chars.next() {
+		block.push(c);
+		if c == '\\' {
+			block.extend(chars.next());
+		} else if c == quote {
+			return;
+		}
+	}
+}
+
+// Copies a raw string of a block, from the char after its r to the end of the string
+fn copy_raw_string(chars: &mut Peekable<Chars>, block: &mut String) {
+	let mut nb_hashes = 0;
+	while let Some('#') = chars.peek() {
+		block.push(chars.next().unwrap());
+		nb_hashes += 1;
+	}
+	block.extend(chars.next());
+	while let Some(c) = chars.next() {
+		block.push(c);
+		if c == '"' {
+			let mut hashes = 0;
+			while hashes < nb_hashes && chars.peek() == Some(&'#') {
+				block.push(chars.next().unwrap());
+				hashes += 1;
 			}
-			// while token
-			'w' => {
-				//while token
-				//take the 5 next chars
-				let mut while_ = String::new();
-				for _ in 0..5 {
-					while_.push(chars.next().unwrap());
+			if hashes == nb_hashes {
+				return;
+			}
+		}
+	}
+}
+
+// Reads the longest glyph of an operator starting with this character
+fn read_glyph(c: char, chars: &mut Peekable<Chars>, operators: &Operators) -> Option<String> {
+	let glyph = operators
+		.starting_with(c)
+		.iter()
+		.filter(|glyph| {
+			let mut rest = chars.clone();
+			glyph.chars().skip(1).all(|x| rest.next() == Some(x))
+		})
+		.max_by_key(|glyph| glyph.chars().count())?
+		.clone();
+	for _ in 1..glyph.chars().count() {
+		chars.next();
+	}
+	Some(glyph)
+}
+
+// Integers too big for 32 bits are longs
+fn integer_token(number: &str) -> Token {
+	if let Ok(x) = number.parse::<i32>() {
+		Token::Integer(x)
+	} else if let Ok(x) = number.parse::<i64>() {
+		Token::Long(x)
+	} else {
+		panic!("Integer literal {} is too big", number);
+	}
+}
+
+// The tokens of single letter operators and sets glued together or to a number,
+// like vv, vN or v5, which are lexed as a name
+// the name is only split when it isn't the one of a word or a macro
+pub fn glued_operators(name: &str, operators: &Operators) -> Option<Vec<Token>> {
+	let mut tokens = Vec::new();
+	let mut chars = name.chars().peekable();
+	while let Some(c) = chars.next() {
+		let glyph = c.to_string();
+		if c.is_ascii_digit() {
+			let mut number = glyph;
+			while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+				number.push(digit);
+			}
+			tokens.push(integer_token(&number));
+		} else if let Some((_, set)) = SETS.iter().find(|(x, _)| *x == glyph) {
+			tokens.push(Token::Set(*set));
+		} else {
+			let operator = operators.get(&glyph)?;
+			tokens.push(Token::Operator(glyph, operator));
+		}
+	}
+	Some(tokens)
+}
+
+// Reports the characters of the code the lexer doesn't know, at their line and column,
+// once for the whole code before it runs
+pub fn check_chars(source: &str, lexing: Lexing, operators: &Operators) {
+	for (c, line, column) in unknown_chars(source, operators) {
+		match lexing {
+			Lexing::Strict => panic!(
+				"Unknown character '{}' at line {}, column {}",
+				c, line, column
+			),
+			Lexing::Lenient => eprintln!(
+				"Warning : ignoring unknown character '{}' at line {}, column {}",
+				c, line, column
+			),
+		}
+	}
+}
+
+// checkpoint saves the program between two instructions of its code, so it can't be used in blocks,
+// which is checked once for the whole code before it runs
+pub fn check_checkpoints(source: &str) {
+	let code = source.chars().collect::<Vec<char>>();
+	let literals = preprocessor::literals(&code);
+	let name = "checkpoint".chars().collect::<Vec<char>>();
+	let mut nb_parenthesis = 0;
+	for i in 0..code.len() {
+		match code[i] {
+			_ if literals[i] => {}
+			'(' => nb_parenthesis += 1,
+			')' if nb_parenthesis > 0 => nb_parenthesis -= 1,
+			_ if nb_parenthesis > 0 && preprocessor::is_name_at(&code, &name, i) => {
+				let line = code[..i].iter().filter(|&&c| c == '\n').count() + 1;
+				panic!(
+					"Cannot use checkpoint inside a block, at line {}, only between instructions of the program",
+					line
+				);
+			}
+			_ => {}
+		}
+	}
+}
+
+// The characters the lexer doesn't know with their line and column,
+// the ones in blocks included though blocks are lexed when they run
+fn unknown_chars(source: &str, operators: &Operators) -> Vec<(char, usize, usize)> {
+	let literals = preprocessor::literals(&source.chars().collect::<Vec<char>>());
+	let mut unknown = Vec::new();
+	let (mut line, mut column) = (1, 0);
+	let mut nb_parenthesis = 0;
+	// chars left in the glyph being read
+	let mut glyph_left = 0;
+	for (i, (offset, c)) in source.char_indices().enumerate() {
+		if c == '\n' {
+			line += 1;
+			column = 0;
+		} else {
+			column += 1;
+		}
+		if glyph_left > 0 {
+			glyph_left -= 1;
+			continue;
+		}
+		match c {
+			_ if literals[i] => {}
+			'(' => nb_parenthesis += 1,
+			')' if nb_parenthesis > 0 => nb_parenthesis -= 1,
+			'§' | 'ℂ' | '¤' | '⋱' | '→' | '←' | '_' | '.' => {}
+			c if c.is_alphanumeric() || WHITESPACE.contains(&c) => {}
+			c => {
+				let mut rest = source[offset + c.len_utf8()..].chars().peekable();
+				match read_glyph(c, &mut rest, operators) {
+					Some(glyph) => glyph_left = glyph.chars().count() - 1,
+					None => unknown.push((c, line, column)),
 				}
-				if while_ == "hile " {
-					tokens.push(Token::Operator(|program| {
-						let condition = program.pop().unwrap();
-						let mut condition = condition.get_integer();
-						let while_code = program.pop().unwrap();
-
-						let mut program_string = String::new();
-						if let NDArray::NDArray { shape, inner } = while_code {
-							for x in inner {
-								program_string.push(x.get_char());
-							}
-						} else {
-							program_string.push(while_code.get_char());
-						}
+			}
+		}
+	}
+	unknown
+}
+
+//divide
+fn divide(program: &mut Program) {
+	let a = program.pop().unwrap();
+	let b = program.pop().unwrap();
+	let a = a.get_single_value();
+	let b = b.get_single_value();
+	program.push(NDArray::division(a, b, program.overflow));
+}
+
+//get current element
+fn current(program: &mut Program) {
+	//get the current element
+	let indices = &program.indices_current;
+	let mut current = program.stack[indices[0]].clone();
+	for i in indices.iter().skip(1) {
+		current = current.get(&[*i]);
+	}
+	program.push(current);
+}
+
+//swap the two top elements
+fn swap(program: &mut Program) {
+	let a = program.pop().unwrap();
+	let b = program.pop().unwrap();
+	program.push(a);
+	program.push(b);
+}
+
+// rot : a b c -> b c a
+fn rot(program: &mut Program) {
+	let c = program.pop().unwrap();
+	let b = program.pop().unwrap();
+	let a = program.pop().unwrap();
+	program.push(b);
+	program.push(c);
+	program.push(a);
+}
+
+// -rot : a b c -> c a b
+fn unrot(program: &mut Program) {
+	let c = program.pop().unwrap();
+	let b = program.pop().unwrap();
+	let a = program.pop().unwrap();
+	program.push(c);
+	program.push(a);
+	program.push(b);
+}
+
+// over : a b -> a b a
+fn over(program: &mut Program) {
+	let b = program.pop().unwrap();
+	let a = program.pop().unwrap();
+	program.push(a.clone());
+	program.push(b);
+	program.push(a);
+}
+
+// nip : a b -> b
+fn nip(program: &mut Program) {
+	let b = program.pop().unwrap();
+	program.pop().unwrap();
+	program.push(b);
+}
 
-						while condition != 0 {
-							let mut other_program = Program::subprogram(&program_string, program);
-							other_program.execute();
-							let result = other_program.pop().unwrap();
-							program.push(result);
-							let _condition = program.pop().unwrap();
-							condition = _condition.get_integer();
+// tuck : a b -> b a b
+fn tuck(program: &mut Program) {
+	let b = program.pop().unwrap();
+	let a = program.pop().unwrap();
+	program.push(b.clone());
+	program.push(a);
+	program.push(b);
+}
+
+// pick : xn ... x0 n -> xn ... x0 xn
+fn pick(program: &mut Program) {
+	let n = program.pop().unwrap().get_integer() as usize;
+	let depth = program.stack.len() - 1;
+	if n >= depth {
+		panic!("Cannot pick {} in a stack of {} elements", n, depth);
+	}
+	let value = program.stack[depth - n].clone();
+	program.push(value);
+}
+
+// roll : xn ... x0 n -> xn-1 ... x0 xn
+fn roll(program: &mut Program) {
+	let n = program.pop().unwrap().get_integer() as usize;
+	let depth = program.stack.len() - 1;
+	if n >= depth {
+		panic!("Cannot roll {} in a stack of {} elements", n, depth);
+	}
+	let mut values = Vec::new();
+	for _ in 0..=n {
+		values.push(program.pop().unwrap());
+	}
+	let rolled = values.pop().unwrap();
+	for value in values.into_iter().rev() {
+		program.push(value);
+	}
+	program.push(rolled);
+}
+
+// depth : number of elements on the stack, without the code
+fn depth(program: &mut Program) {
+	let depth = program.stack.len() - 1;
+	program.push(NDArray::SingleValue(SingleValue::Integer(depth as i32)));
+}
+
+// clear : removes everything from the stack but the code
+fn clear(program: &mut Program) {
+	while program.stack.len() > 1 {
+		program.pop();
+	}
+}
+
+// comparisons of the top of the stack with the element below
+fn less(program: &mut Program) {
+	let a = ordered(program.pop().unwrap().get_single_value());
+	let b = ordered(program.pop().unwrap().get_single_value());
+	program.push(NDArray::SingleValue(SingleValue::Bool(a < b)));
+}
+
+fn greater(program: &mut Program) {
+	let a = ordered(program.pop().unwrap().get_single_value());
+	let b = ordered(program.pop().unwrap().get_single_value());
+	program.push(NDArray::SingleValue(SingleValue::Bool(a > b)));
+}
+
+// complex numbers can't be compared with < and >
+fn ordered(x: SingleValue) -> SingleValue {
+	if let SingleValue::Complex(..) = x {
+		panic!("Cannot order complex numbers");
+	}
+	x
+}
+
+// reshape operator
+fn reshape(program: &mut Program) {
+	let nb_dims = program.pop().unwrap().get_integer();
+	let mut shape = Vec::new();
+	for _ in 0..nb_dims {
+		let dim = program.pop().unwrap().get_integer();
+		shape.push(dim as usize);
+	}
+	let mut values = Vec::new();
+	for _ in 0..shape.iter().product() {
+		values.push(program.pop().unwrap());
+	}
+
+	let mut array = NDArray::zeros(&shape);
+	let total = shape.iter().product();
+	for i in (0..total).rev() {
+		let mut indices = vec![0; shape.len()];
+		let mut index = i;
+		for j in 0..shape.len() {
+			let dim = shape[j];
+			indices[j] = index % dim;
+			index /= dim;
+		}
+		let indices = indices.iter().rev().map(|x| *x).collect::<Vec<_>>();
+		array.set(&indices, values[total - i - 1].clone());
+	}
+	program.push(array);
+}
+
+// fold left operator with window
+fn fold(program: &mut Program) {
+	let operation = program.pop().unwrap();
+	let window_size = program.pop().unwrap().get_integer();
+	let identity = program.pop().unwrap();
+	let array = program.pop().unwrap();
+
+	let mut array = if let NDArray::NDArray { shape, inner } = array {
+		shape;
+		inner
+	} else {
+		panic!("Expected an array");
+	};
+
+	let mut result = identity;
+	let mut window = Vec::new();
+	for i in 0..array.len() {
+		window.push(array[i].clone());
+		if window.len() == window_size as usize {
+			let mut program_string = String::new();
+			if let NDArray::NDArray { shape, inner } = &operation {
+				for x in inner {
+					match x {
+						NDArray::SingleValue(SingleValue::Char(x)) => {
+							program_string.push(*x);
 						}
-					}));
+						_ => panic!("Expected a program"),
+					}
 				}
+			} else {
+				panic!("Expected a program");
 			}
-			// modulo
-			'%' => {
-				tokens.push(Token::Operator(|program| {
-					let a = program.pop().unwrap();
-					let b = program.pop().unwrap();
-					let a = a.get_integer();
-					let b = b.get_integer();
-					dbg!(a);
-					dbg!(b);
-					program.push(NDArray::SingleValue(SingleValue::Integer(a % b)));
-				}));
+			let mut other_program = Program::subprogram(&program_string, program);
+			other_program.push(result);
+			for x in window.iter().rev() {
+				other_program.push(x.clone());
 			}
-			// map operator
-			'≝' => {}
-			_ => {}
+			other_program.execute();
+			result = other_program.get_result();
+			window.remove(0);
 		}
 	}
-	tokens
+
+	program.push(result);
+}
+
+fn index(program: &mut Program) {
+	let indices = program.pop().unwrap();
+	if let NDArray::SingleValue(SingleValue::Integer(x)) = indices {
+		let value = program.stack.get(x as usize).unwrap().clone();
+		program.push(value);
+		return;
+	}
+	//get the current element
+	let mut current = program.stack[indices.get(&[0]).get_integer() as usize].clone();
+	for i in 1..indices.shape()[0] {
+		current = current.get(&[indices.get(&[i]).get_integer() as usize]);
+	}
+
+	program.push(current);
+}
+
+// real part, imaginary part, magnitude and phase of every element
+fn real_part(program: &mut Program) {
+	let a = program.pop().unwrap();
+	program.push(a.map(&|x| SingleValue::Double(x.get_complex().0)));
+}
+
+fn imaginary_part(program: &mut Program) {
+	let a = program.pop().unwrap();
+	program.push(a.map(&|x| SingleValue::Double(x.get_complex().1)));
+}
+
+fn magnitude(program: &mut Program) {
+	let a = program.pop().unwrap();
+	program.push(a.map(&|x| {
+		let (re, im) = x.get_complex();
+		SingleValue::Double(re.hypot(im))
+	}));
+}
+
+fn phase(program: &mut Program) {
+	let a = program.pop().unwrap();
+	program.push(a.map(&|x| {
+		let (re, im) = x.get_complex();
+		SingleValue::Double(im.atan2(re))
+	}));
+}
+
+fn add(program: &mut Program) {
+	let a = program.pop().unwrap();
+	let b = program.pop().unwrap();
+	let a = a.get_single_value();
+	let b = b.get_single_value();
+	program.push(NDArray::addition(a, b, program.overflow));
+}
+
+fn substract(program: &mut Program) {
+	let a = program.pop().unwrap();
+	let b = program.pop().unwrap();
+	let a = a.get_single_value();
+	let b = b.get_single_value();
+	program.push(NDArray::substraction(a, b, program.overflow));
+}
+
+fn multiply(program: &mut Program) {
+	let a = program.pop().unwrap();
+	let b = program.pop().unwrap();
+	let a = a.get_single_value();
+	let b = b.get_single_value();
+	program.push(NDArray::multiplication(a, b, program.overflow));
+}
+
+// checked addition, whatever the overflow mode of the program
+fn checked_add(program: &mut Program) {
+	let a = program.pop().unwrap().get_single_value();
+	let b = program.pop().unwrap().get_single_value();
+	program.push(NDArray::addition(a, b, Overflow::Checked));
+}
+
+// checked substraction, whatever the overflow mode of the program
+fn checked_substract(program: &mut Program) {
+	let a = program.pop().unwrap().get_single_value();
+	let b = program.pop().unwrap().get_single_value();
+	program.push(NDArray::substraction(a, b, Overflow::Checked));
+}
+
+// wrapping addition, whatever the overflow mode of the program
+fn wrapping_add(program: &mut Program) {
+	let a = program.pop().unwrap().get_single_value();
+	let b = program.pop().unwrap().get_single_value();
+	program.push(NDArray::addition(a, b, Overflow::Wrapping));
+}
+
+// wrapping substraction, whatever the overflow mode of the program
+fn wrapping_substract(program: &mut Program) {
+	let a = program.pop().unwrap().get_single_value();
+	let b = program.pop().unwrap().get_single_value();
+	program.push(NDArray::substraction(a, b, Overflow::Wrapping));
+}
+
+// saturating addition, whatever the overflow mode of the program
+fn saturating_add(program: &mut Program) {
+	let a = program.pop().unwrap().get_single_value();
+	let b = program.pop().unwrap().get_single_value();
+	program.push(NDArray::addition(a, b, Overflow::Saturating));
+}
+
+// saturating substraction, whatever the overflow mode of the program
+fn saturating_substract(program: &mut Program) {
+	let a = program.pop().unwrap().get_single_value();
+	let b = program.pop().unwrap().get_single_value();
+	program.push(NDArray::substraction(a, b, Overflow::Saturating));
+}
+
+// checked multiplication, whatever the overflow mode of the program
+fn checked_multiply(program: &mut Program) {
+	let a = program.pop().unwrap().get_single_value();
+	let b = program.pop().unwrap().get_single_value();
+	program.push(NDArray::multiplication(a, b, Overflow::Checked));
+}
+
+// wrapping multiplication, whatever the overflow mode of the program
+fn wrapping_multiply(program: &mut Program) {
+	let a = program.pop().unwrap().get_single_value();
+	let b = program.pop().unwrap().get_single_value();
+	program.push(NDArray::multiplication(a, b, Overflow::Wrapping));
+}
+
+// saturating multiplication, whatever the overflow mode of the program
+fn saturating_multiply(program: &mut Program) {
+	let a = program.pop().unwrap().get_single_value();
+	let b = program.pop().unwrap().get_single_value();
+	program.push(NDArray::multiplication(a, b, Overflow::Saturating));
+}
+
+// "wrapping" overflow : sets how the integers of +, -, × and / overflow in the program
+// checked, wrapping or saturating
+fn set_overflow(program: &mut Program) {
+	let mode = program.pop().unwrap().get_string();
+	program.overflow = Overflow::from_name(&mode);
+}
+
+//copy operator
+fn copy(program: &mut Program) {
+	let a = program.pop().unwrap();
+	program.push(a.clone());
+	program.push(a.clone());
+}
+
+// create an ndarray
+fn zeros(program: &mut Program) {
+	let nb_dims = program.pop().unwrap().get_integer();
+	let mut shape = Vec::new();
+	for _ in 0..nb_dims {
+		let dim = program.pop().unwrap().get_integer();
+		shape.push(dim as usize);
+	}
+	let array = NDArray::zeros(&shape);
+	program.push(array);
+}
+
+// modulo
+fn modulo(program: &mut Program) {
+	let a = program.pop().unwrap();
+	let b = program.pop().unwrap();
+	let a = a.get_integer();
+	let b = b.get_integer();
+	program.push(NDArray::SingleValue(SingleValue::Integer(a % b)));
+}
+
+// define a word : (code) "name" ≝
+fn define_word(program: &mut Program) {
+	let name = program.pop().unwrap().get_string();
+	let code = program.pop().unwrap().get_string();
+	program.words.insert(name, code);
+}
+
+// define a real time macro : (code) "name" ≜
+// the code leaves the char array to execute on top of the stack
+// a word of the same name shadows the macro
+fn define_macro(program: &mut Program) {
+	let name = program.pop().unwrap().get_string();
+	let code = program.pop().unwrap().get_string();
+	program.macros.insert(name, code);
+}
+
+// double equal
+fn equal(program: &mut Program) {
+	let a = program.pop().unwrap();
+	let b = program.pop().unwrap();
+	let a = a.get_single_value();
+	let b = b.get_single_value();
+	program.push(NDArray::SingleValue(SingleValue::Bool(a == b)));
+}
+
+// map operator : array (code) =
+fn map(program: &mut Program) {
+	let code = program.pop().unwrap();
+	let array = program.pop().unwrap();
+
+	//print the code
+	let mut program_string = String::new();
+	if let NDArray::NDArray { shape, inner } = &code {
+		for x in inner {
+			match x {
+				NDArray::SingleValue(SingleValue::Char(x)) => {
+					program_string.push(*x);
+				}
+				_ => panic!("Expected a program"),
+			}
+		}
+	} else {
+		panic!("Expected a program");
+	}
+
+	let (array_shape, mut array_inner) = if let NDArray::NDArray { shape, inner } = array {
+		(shape, inner)
+	} else {
+		panic!("Expected an array");
+	};
+
+	// for each element in the array
+	// execute the code
+
+	for i in 0..array_shape[0] {
+		program.indices_current.push(i);
+		let mut other_program = Program::subprogram(&program_string, program);
+		other_program.execute();
+		let result_element = other_program.get_result();
+		// set the result in the new array
+		array_inner[i] = result_element;
+		program.indices_current.pop();
+	}
+
+	program.push(NDArray::NDArray {
+		shape: array_shape.clone(),
+		inner: array_inner,
+	});
+}
+
+// index of the current element of the arrays being mapped, from the outermost one
+fn current_index(program: &mut Program) {
+	let i = program.pop().unwrap();
+	let i = i.get_integer();
+	// negative indices count from the innermost one
+	let i = if i < 0 {
+		program.indices_current.len() as i32 + i
+	} else {
+		i
+	};
+	let result = program.indices_current[i as usize];
+	let result = NDArray::SingleValue(SingleValue::Integer(result as i32));
+	program.push(result);
+}
+
+// pop
+fn drop(program: &mut Program) {
+	program.pop();
+}
+
+fn debug(program: &mut Program) {
+	println!("DEBUG");
+	dbg!(&program);
+}
+
+// (else code) (if code) condition ifelse
+fn ifelse(program: &mut Program) {
+	let condition = program.pop().unwrap();
+	let condition = condition.get_condition();
+	let if_code = program.pop().unwrap();
+	let else_code = program.pop().unwrap();
+
+	let bloc_to_execute = if condition { if_code } else { else_code };
+	//turn the bloc into a program
+	let mut program_string = String::new();
+	if let NDArray::NDArray { shape, inner } = bloc_to_execute {
+		for x in inner {
+			program_string.push(x.get_char());
+		}
+	} else {
+		program_string.push(bloc_to_execute.get_char());
+	}
+	let mut other_program = Program::subprogram(&program_string, program);
+	other_program.loops = program.loops;
+	other_program.execute();
+	// a break or continue in the bloc is for the loop around the ifelse
+	if other_program.control.is_some() {
+		program.control = other_program.control;
+		return;
+	}
+	let result = other_program.pop().unwrap();
+	program.push(result);
+}
+
+// (condition) (code) while
+// runs the condition then the code as long as the condition is true
+// both run on the stack of the program, the condition's result is popped
+fn while_loop(program: &mut Program) {
+	let code = program.pop().unwrap().get_string();
+	let condition = program.pop().unwrap().get_string();
+	loop {
+		program.execute_code(&condition);
+		if !program.pop().unwrap().get_condition() {
+			break;
+		}
+		if program.execute_iteration(&code) == Some(Control::Break) {
+			break;
+		}
+	}
+}
+
+// (code) (condition) until
+// runs the code then the condition until the condition is true
+fn until(program: &mut Program) {
+	let condition = program.pop().unwrap().get_string();
+	let code = program.pop().unwrap().get_string();
+	loop {
+		if program.execute_iteration(&code) == Some(Control::Break) {
+			break;
+		}
+		program.execute_code(&condition);
+		if program.pop().unwrap().get_condition() {
+			break;
+		}
+	}
+}
+
+// n (code) times
+// runs the code n times, with the iteration number as the innermost index
+fn times(program: &mut Program) {
+	let code = program.pop().unwrap().get_string();
+	let n = program.pop().unwrap().get_integer();
+	for i in 0..n.max(0) as usize {
+		program.indices_current.push(i);
+		let control = program.execute_iteration(&code);
+		program.indices_current.pop();
+		if control == Some(Control::Break) {
+			break;
+		}
+	}
+}
+
+// array (code) each
+// runs the code with every element of the array pushed on the stack,
+// with the index of the element as the innermost index
+fn each(program: &mut Program) {
+	let code = program.pop().unwrap().get_string();
+	let array = program.pop().unwrap();
+	let inner = match array {
+		NDArray::NDArray { inner, .. } => inner,
+		NDArray::SingleValue(_) => panic!("Expected an array"),
+	};
+	for (i, x) in inner.into_iter().enumerate() {
+		program.indices_current.push(i);
+		program.push(x);
+		let control = program.execute_iteration(&code);
+		program.indices_current.pop();
+		if control == Some(Control::Break) {
+			break;
+		}
+	}
+}
+
+// only in the body of a loop, the code of = and ¨ isn't one
+fn break_loop(program: &mut Program) {
+	if program.loops == 0 {
+		panic!("Cannot break outside of a loop");
+	}
+	program.control = Some(Control::Break);
+}
+
+fn continue_loop(program: &mut Program) {
+	if program.loops == 0 {
+		panic!("Cannot continue outside of a loop");
+	}
+	program.control = Some(Control::Continue);
+}
+
+// pops the top of the stack and prints it
+fn print(program: &mut Program) {
+	let value = program.pop().unwrap();
+	print!("{}", value.format(&program.format));
+	io::stdout().flush().unwrap();
+}
+
+// pops a char or char array and prints it as text
+fn write(program: &mut Program) {
+	let text = match program.pop().unwrap() {
+		NDArray::SingleValue(SingleValue::Char(c)) => c.to_string(),
+		text => text.get_string(),
+	};
+	print!("{}", text);
+	io::stdout().flush().unwrap();
+}
+
+fn newline(_program: &mut Program) {
+	println!();
+}
+
+// pushes the next line of stdin as a char array, without its line break,
+// then whether there was a line : false with an empty array at the end of stdin
+// (readline) (write newline) while : writes every line of stdin
+fn read_line(program: &mut Program) {
+	push_line(program, &mut io::stdin().lock());
+}
+
+fn push_line(program: &mut Program, input: &mut impl BufRead) {
+	let mut line = String::new();
+	let nb_bytes = input
+		.read_line(&mut line)
+		.unwrap_or_else(|error| panic!("Cannot read stdin : {}", error));
+	if line.ends_with('\n') {
+		line.pop();
+		if line.ends_with('\r') {
+			line.pop();
+		}
+	}
+	program.push(NDArray::from_1d_char(line.chars().collect()));
+	program.push(NDArray::SingleValue(SingleValue::Bool(nb_bytes != 0)));
+}
+
+// pushes the rest of stdin as a char array
+fn read_all(program: &mut Program) {
+	let mut input = String::new();
+	io::stdin()
+		.read_to_string(&mut input)
+		.unwrap_or_else(|error| panic!("Cannot read stdin : {}", error));
+	program.push(NDArray::from_1d_char(input.chars().collect()));
+}
+
+// pushes the JSON text of the top of the stack as a char array
+fn to_json(program: &mut Program) {
+	let json = program.pop().unwrap().to_json();
+	program.push(NDArray::from_1d_char(json.chars().collect()));
+}
+
+// pushes the value of the JSON text in the char array at the top of the stack
+fn from_json(program: &mut Program) {
+	let json = program.pop().unwrap().get_string();
+	program.push(NDArray::from_json(&json));
+}
+
+// "file.npy" loadnpy : pushes the array of a .npy file
+fn load_npy(program: &mut Program) {
+	let path = program.pop().unwrap().get_string();
+	program.push(NDArray::load_npy(&path));
+}
+
+// array "file.npy" savenpy : writes the array in a .npy file
+fn save_npy(program: &mut Program) {
+	let path = program.pop().unwrap().get_string();
+	program.pop().unwrap().save_npy(&path);
+}
+
+// "file.csv" header loadcsv : pushes the 2-D array of a CSV file
+// its first line is skipped when the header condition is true
+fn load_csv(program: &mut Program) {
+	let header = program.pop().unwrap().get_condition();
+	let path = program.pop().unwrap().get_string();
+	program.push(NDArray::load_csv(&path, header));
+}
+
+// array "file.csv" savecsv : writes a 2-D array in a CSV file
+fn save_csv(program: &mut Program) {
+	let path = program.pop().unwrap().get_string();
+	program.pop().unwrap().save_csv(&path);
+}
+
+// "file" checkpoint : saves a snapshot of the program that resumes after the checkpoint
+// only at the top level of the code of the program, checkpoints in blocks are errors before it runs
+// and words calling it can't take the snapshot either
+fn checkpoint(program: &mut Program) {
+	let path = program.pop().unwrap().get_string();
+	program.current_instruction += 1;
+	program.save_snapshot(&path);
+	program.current_instruction -= 1;
+}
+
+// Reads a comment after its ⍝, up to the end of the line
+fn read_line_comment(chars: &mut Peekable<Chars>) -> String {
+	let mut comment = String::new();
+	while let Some(&c) = chars.peek() {
+		if c == '\n' {
+			break;
+		}
+		comment.push(c);
+		chars.next();
+	}
+	comment
+}
+
+// Reads a comment after its {- and consumes its -}, with the nested comments inside it
+fn read_block_comment(chars: &mut Peekable<Chars>) -> String {
+	let mut comment = String::new();
+	let mut depth = 1;
+	while let Some(c) = chars.next() {
+		if c == '{' && chars.peek() == Some(&'-') {
+			depth += 1;
+		} else if c == '-' && chars.peek() == Some(&'}') {
+			depth -= 1;
+			if depth == 0 {
+				chars.next();
+				return comment;
+			}
+		}
+		comment.push(c);
+	}
+	panic!("Expected -}} to close comment");
+}
+
+// Reads the rest of a name made of letters, digits, _ and . for namespaces
+// a . is only part of the name when the start of a name follows, foo.25 is foo then .25
+fn read_name(chars: &mut Peekable<Chars>, name: &mut String) {
+	while let Some(&c) = chars.peek() {
+		let is_name_char = match c {
+			'.' => {
+				let mut rest = chars.clone();
+				rest.next();
+				rest.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+			}
+			c => c.is_alphanumeric() || c == '_',
+		};
+		if !is_name_char {
+			break;
+		}
+		name.push(c);
+		chars.next();
+	}
+}
+
+fn read_variable_name(chars: &mut Peekable<Chars>) -> String {
+	let mut name = String::new();
+	read_name(chars, &mut name);
+	if name.is_empty() {
+		panic!("Expected a variable name after → or ←");
+	}
+	name
+}
+
+// Real literals with more significant digits than a f32 can hold become doubles
+// and real literals followed by a j are imaginary numbers
+fn real_token(number: &str, chars: &mut Peekable<Chars>) -> Token {
+	if let Some('j') = chars.peek() {
+		chars.next();
+		return Token::Complex(0.0, number.parse::<f64>().unwrap());
+	}
+	if is_double(number) {
+		Token::Double(number.parse::<f64>().unwrap())
+	} else {
+		Token::Real(number.parse::<f32>().unwrap())
+	}
+}
+
+// Reals with more than 7 significant digits don't fit in a f32
+pub(crate) fn is_double(number: &str) -> bool {
+	let mantissa = number.split(['e', 'E']).next().unwrap();
+	let nb_digits = mantissa
+		.trim_start_matches(['-', '+'])
+		.trim_start_matches(['0', '.'])
+		.chars()
+		.filter(|c| c.is_ascii_digit())
+		.count();
+	nb_digits > 7
+}
+
+// Reads the char following a \ in a string or char literal
+fn read_escape(chars: &mut Peekable<Chars>) -> char {
+	match chars.next() {
+		Some('n') => '\n',
+		Some('t') => '\t',
+		Some('r') => '\r',
+		Some('0') => '\0',
+		Some('\\') => '\\',
+		Some('"') => '"',
+		Some('\'') => '\'',
+		Some('u') => {
+			// \u{...} with the code point in hexadecimal
+			if chars.next() != Some('{') {
+				panic!("Expected {{ after \\u");
+			}
+			let mut code_point = String::new();
+			loop {
+				match chars.next() {
+					Some('}') => break,
+					Some(c) => code_point.push(c),
+					None => panic!("Expected closing }} in unicode escape"),
+				}
+			}
+			u32::from_str_radix(&code_point, 16)
+				.ok()
+				.and_then(char::from_u32)
+				.unwrap_or_else(|| panic!("Invalid unicode escape \\u{{{}}}", code_point))
+		}
+		Some(c) => panic!("Unknown escape sequence \\{}", c),
+		None => panic!("Expected an escape sequence after \\"),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::error;
+	use crate::ndarray::tests::typed;
+	use crate::preprocessor::Preprocessor;
+	use crate::program::Options;
+
+	fn tokens(source: &str) -> Vec<Token> {
+		tokenize(source, Lexing::Strict, &builtin_operators())
+	}
+
+	fn string(source: &str) -> String {
+		match tokens(source).as_slice() {
+			[Token::String(x)] => x.clone(),
+			x => panic!("Expected a string, got {:?}", x),
+		}
+	}
+
+	fn char(source: &str) -> char {
+		match tokens(source).as_slice() {
+			[Token::Char(x)] => *x,
+			x => panic!("Expected a char, got {:?}", x),
+		}
+	}
+
+	#[test]
+	fn escapes() {
+		assert_eq!(string(r#""a\nb\tc\rd""#), "a\nb\tc\rd");
+		assert_eq!(string(r#""\"quoted\" \\ \'""#), "\"quoted\" \\ '");
+		assert_eq!(string(r#""\u{48}\u{e9}\u{1F600}\0""#), "Hé😀\0");
+		assert_eq!(char(r"'\n'"), '\n');
+		assert_eq!(char(r"'\''"), '\'');
+		assert_eq!(char(r"'\u{3bb}'"), 'λ');
+		assert_eq!(char("'\"'"), '"');
+	}
+
+	#[test]
+	#[should_panic(expected = "Unknown escape sequence \\q")]
+	fn unknown_escape() {
+		tokens(r#""\q""#);
+	}
+
+	#[test]
+	#[should_panic(expected = "Invalid unicode escape \\u{d800}")]
+	fn invalid_unicode_escape() {
+		tokens(r#""\u{d800}""#);
+	}
+
+	#[test]
+	#[should_panic(expected = "Expected closing \"")]
+	fn unclosed_string() {
+		tokens(r#""abc\""#);
+	}
+
+	#[test]
+	fn raw_strings() {
+		assert_eq!(string(r#"r"a\nb""#), "a\\nb");
+		assert_eq!(string(r##"r#"say "hi""#"##), "say \"hi\"");
+		assert_eq!(string(r###"r##"a "# b"##"###), "a \"# b");
+		assert_eq!(string(r#"r"""#), "");
+	}
+
+	#[test]
+	fn unknown_characters() {
+		let unknown = |source| unknown_chars(source, &builtin_operators());
+		assert_eq!(unknown("1 ¶ 2"), [('¶', 1, 3)]);
+		// in blocks, at their position in the source
+		assert_eq!(unknown("1\n(2 (3\n\t¶)) ¶"), [('¶', 3, 2), ('¶', 3, 6)]);
+		// but not in literals and comments
+		assert!(unknown("\"¶\" '¶' r\"¶\" ⍝ ¶\n{- ¶ -} (\"¶)\")").is_empty());
+		// glyphs of several chars are known as a whole
+		assert!(unknown("1 1 == 0 []").is_empty());
+		assert_eq!(unknown("1 ] )"), [(']', 1, 3), (')', 1, 5)]);
+		// the blocks that never run are checked
+		let strict = |source| {
+			let options = Options {
+				strict: true,
+				..Options::default()
+			};
+			let mut program = Program::with_preprocessor(source, Preprocessor::new(), &options);
+			program.run().map_err(|error| error.message)
+		};
+		assert_eq!(
+			strict("(1 ¶) \"s\" ≝ 5"),
+			Err("Unknown character '¶' at line 1, column 4".into())
+		);
+		// code made while the program runs is checked when it's lexed
+		assert_eq!(
+			strict("\"1 ¶\" \"s\" ≝ s"),
+			Err("Unknown character '¶'".into())
+		);
+		// they're skipped in lenient mode
+		let mut program = Program::new("0 3 (1 ¶ +) times");
+		program.run().unwrap();
+		assert_eq!(program.get_result().get_integer(), 3);
+	}
+
+	#[test]
+	fn literals_in_blocks() {
+		let block = |source| match tokens(source).as_slice() {
+			[Token::String(x), ..] => x.clone(),
+			x => panic!("Expected a block, got {:?}", x),
+		};
+		assert_eq!(block("(\"a⍝b\") \"s\" ≝"), "\"a⍝b\"");
+		assert_eq!(tokens("(\"a⍝b\") \"s\" ≝").len(), 3);
+		assert_eq!(block("(\"a{-b\")"), "\"a{-b\"");
+		assert_eq!(
+			block("(\")\" '(' ')' '\\'' \"\\\")\")"),
+			"\")\" '(' ')' '\\'' \"\\\")\""
+		);
+		assert_eq!(
+			block("(r\"a)b\" r#\"c\")\"# ⍝ )\n)"),
+			"r\"a)b\" r#\"c\")\"# ⍝ )\n"
+		);
+		// a r at the end of a name doesn't start a raw string
+		assert_eq!(block("(for\"x\" 1) 2"), "for\"x\" 1");
+		let mut program = Program::new("(\"a⍝b\" \"(\") \"s\" ≝ s");
+		program.execute();
+		assert_eq!(program.values()[0].get_string(), "a⍝b");
+		assert_eq!(program.get_result().get_string(), "(");
+	}
+
+	#[test]
+	fn literal_widths() {
+		let literals = tokens("2147483647 2147483648 1.5 .25 3.14159265 0.0001234567 2.5j");
+		assert!(matches!(literals[0], Token::Integer(2147483647)));
+		assert!(matches!(literals[1], Token::Long(2147483648)));
+		assert!(matches!(literals[2], Token::Real(x) if x == 1.5));
+		assert!(matches!(literals[3], Token::Real(x) if x == 0.25));
+		assert!(matches!(literals[4], Token::Double(x) if x == 3.14159265));
+		// leading zeros aren't significant digits
+		assert!(matches!(literals[5], Token::Real(x) if x == 0.0001234567));
+		assert!(matches!(literals[6], Token::Complex(re, im) if re == 0.0 && im == 2.5));
+	}
+
+	#[test]
+	fn width_promotion() {
+		let run = |source| {
+			let mut program = Program::new(source);
+			program.execute();
+			program.get_result().get_single_value()
+		};
+		assert!(matches!(
+			run("1 2147483648 +"),
+			SingleValue::Long(2147483649)
+		));
+		assert!(matches!(run("1.5 3.14159265 +"), SingleValue::Double(_)));
+		assert!(matches!(
+			run("2147483647 L § 1 +"),
+			SingleValue::Long(2147483648)
+		));
+		assert!(matches!(run("1.5 D § .5 +"), SingleValue::Double(x) if x == 2.0));
+		// integers operated with reals become reals
+		assert!(matches!(run("1 2.5 +"), SingleValue::Real(x) if x == 3.5));
+		assert!(matches!(run("2.5 2 ×"), SingleValue::Real(x) if x == 5.0));
+		assert!(matches!(run("1 3.14159265 +"), SingleValue::Double(_)));
+		assert!(matches!(run("2147483648 .5 +"), SingleValue::Double(x) if x == 2147483648.5));
+		assert!(matches!(
+			run("2147483647 N §"),
+			SingleValue::Integer(2147483647)
+		));
+	}
+
+	#[test]
+	#[should_panic(expected = "Integer overflow in converting 2147483648 to N")]
+	fn narrowing_overflow() {
+		Program::new("2147483648 N §").execute();
+	}
+
+	#[test]
+	#[should_panic(expected = "Integer literal 99999999999999999999 is too big")]
+	fn oversized_literal() {
+		tokens("99999999999999999999");
+	}
+
+	#[test]
+	fn glued_operators() {
+		let run = |source| {
+			let mut program = Program::new(source);
+			program
+				.run()
+				.map(|_| program.values().iter().map(typed).collect::<Vec<_>>())
+		};
+		assert_eq!(
+			run("1 2 3 4 vv"),
+			Ok(vec!["Integer(1)".into(), "Integer(2)".into()])
+		);
+		assert_eq!(
+			run("1 2 v5"),
+			Ok(vec!["Integer(1)".into(), "Integer(5)".into()])
+		);
+		assert_eq!(run("2.5 7 vN §"), Ok(vec!["Integer(2)".into()]));
+		assert_eq!(run("1 2 v3000000000 vv"), Ok(vec![]));
+		// they're split at run time, when there's no word or macro of that name
+		assert!(matches!(tokens("vdv").as_slice(), [Token::Word(x)] if x == "vdv"));
+		assert_eq!(run("(1 +) \"vv\" ≝ 5 vv"), Ok(vec!["Integer(6)".into()]));
+		assert_eq!(run("(2 ×) \"dv\" ≝ 5 dv"), Ok(vec!["Integer(10)".into()]));
+		assert_eq!(
+			run("(\"1 +\") \"v5\" ≜ 5 v5"),
+			Ok(vec!["Integer(6)".into()])
+		);
+		// other names with these letters are words
+		assert_eq!(run("1 vd2x").unwrap_err().message, "Unknown word vd2x");
+		assert_eq!(run("1 dive").unwrap_err().message, "Unknown word dive");
+	}
+
+	// names starting like an operator or a set are still words
+	#[test]
+	fn word_names() {
+		let names = [
+			"double", "value", "input", "width", "Name", "Result", "Count", "Lines", "Data", "Box",
+		];
+		for name in names {
+			assert!(matches!(tokens(name).as_slice(), [Token::Word(x)] if x == name));
+		}
+		// a . is only part of a name before the start of another name
+		assert!(matches!(tokens("math.inc").as_slice(), [Token::Word(x)] if x == "math.inc"));
+		assert!(matches!(
+			tokens("foo.25").as_slice(),
+			[Token::Word(x), Token::Real(y)] if x == "foo" && *y == 0.25
+		));
+		let mut program = Program::new("(2 ×) \"double\" ≝ (1 +) \"value\" ≝ 3 double value");
+		program.execute();
+		assert_eq!(program.get_result().get_integer(), 7);
+	}
+
+	#[test]
+	fn stack_operators() {
+		let run = |source| {
+			let mut program = Program::new(source);
+			program.run().map(|_| {
+				let values = program.values();
+				values.iter().map(|x| x.get_integer()).collect::<Vec<_>>()
+			})
+		};
+		assert_eq!(run("1 2 3 ↻"), Ok(vec![2, 3, 1]));
+		assert_eq!(run("1 2 3 ↺"), Ok(vec![3, 1, 2]));
+		assert_eq!(run("1 2 ⇈"), Ok(vec![1, 2, 1]));
+		assert_eq!(run("1 2 ⇊"), Ok(vec![2]));
+		assert_eq!(run("1 2 ⇅"), Ok(vec![2, 1, 2]));
+		// pick and roll count from the top of the stack, 0 being the top
+		assert_eq!(run("1 2 3 2 ⇑"), Ok(vec![1, 2, 3, 1]));
+		assert_eq!(run("1 2 3 0 ⇑"), Ok(vec![1, 2, 3, 3]));
+		assert_eq!(run("1 2 3 2 ⟳"), Ok(vec![2, 3, 1]));
+		assert_eq!(run("1 2 3 0 ⟳"), Ok(vec![1, 2, 3]));
+		// the code of the program isn't counted nor cleared
+		assert_eq!(run("4 5 №"), Ok(vec![4, 5, 2]));
+		assert_eq!(run("№"), Ok(vec![0]));
+		assert_eq!(run("1 2 3 ∅"), Ok(vec![]));
+		assert_eq!(run("1 2 ∅ 3 4 +"), Ok(vec![7]));
+		let error = |source| run(source).unwrap_err().message;
+		assert_eq!(error("1 2 2 ⇑"), "Cannot pick 2 in a stack of 2 elements");
+		assert_eq!(error("1 5 ⟳"), "Cannot roll 5 in a stack of 1 elements");
+		assert_eq!(
+			error("1 2 ↻"),
+			"Operator ↻ takes 3 values but the stack has 2"
+		);
+	}
+
+	#[test]
+	fn variables() {
+		let run = |source| {
+			let mut program = Program::new(source);
+			program.run().map(|_| program.get_result().get_integer())
+		};
+		assert_eq!(run("5 →x ←x ←x +"), Ok(10));
+		assert_eq!(run("1 →x 2 →x ←x"), Ok(2));
+		assert_eq!(run("1 →first_value 2 →x2 ←first_value ←x2 -"), Ok(1));
+		// words run on the program, so they read and write its variables
+		assert_eq!(run("(←n 1 + →n) \"inc\" ≝ 1 →n inc inc ←n"), Ok(3));
+		// subprograms get a copy of them, and their writes don't propagate back
+		assert_eq!(run("7 →x 3 1 ⊹ (←x) = first"), Ok(7));
+		assert_eq!(run("1 →x 3 1 ⊹ (2 →x ←x) = v ←x"), Ok(1));
+		assert_eq!(run("←y").unwrap_err().message, "Unknown variable y");
+		let error = error::catch(|| tokens("1 → x")).unwrap_err();
+		assert_eq!(error.message, "Expected a variable name after → or ←");
+	}
+
+	#[test]
+	fn real_time_macros() {
+		let run = |source: &str| {
+			let mut program = Program::new(source);
+			program.run().map(|_| {
+				let values = program.values();
+				values.iter().map(|x| x.get_integer()).collect::<Vec<_>>()
+			})
+		};
+		assert_eq!(run("(\"1 +\") \"inc\" ≜ 1 inc inc"), Ok(vec![3]));
+		// the code is generated from the stack at the time the macro runs
+		let parity = "(→n (\"20\") (\"10\") ←n 2 ~ % 0 == ifelse) \"parity\" ≜ ";
+		assert_eq!(run(&format!("{}4 parity", parity)), Ok(vec![4, 10]));
+		assert_eq!(run(&format!("{}3 parity", parity)), Ok(vec![3, 20]));
+		// on a copy of it, so only the generated code changes the stack
+		assert_eq!(run("(v v v \"+\") \"add\" ≜ 1 2 3 add"), Ok(vec![1, 5]));
+		// words shadow macros of the same name
+		assert_eq!(run("(1 +) \"f\" ≝ (\"2 +\") \"f\" ≜ 0 f"), Ok(vec![1]));
+		assert_eq!(run("(\"2 +\") \"f\" ≜ (1 +) \"f\" ≝ 0 f"), Ok(vec![1]));
+	}
+
+	#[test]
+	fn comparisons() {
+		let run = |source| {
+			let mut program = Program::new(source);
+			program.execute();
+			program.get_result().get_single_value()
+		};
+		// the top of the stack is compared with the element below
+		assert_eq!(run("3 5 <"), SingleValue::Bool(false));
+		assert_eq!(run("3 5 >"), SingleValue::Bool(true));
+		assert_eq!(run("2 2 <"), SingleValue::Bool(false));
+		assert_eq!(run("2 2 =="), SingleValue::Bool(true));
+		assert_eq!(run("2 L § 3 >"), SingleValue::Bool(true));
+		assert_eq!(run("'a' 'b' <"), SingleValue::Bool(false));
+		// their result is a condition
+		assert_eq!(run("(0) (1) 3 5 > ifelse"), SingleValue::Integer(1));
+		assert_eq!(run("0 (© 5 >) (1 +) while"), SingleValue::Integer(5));
+		assert_eq!(run("3 5 > N §"), SingleValue::Integer(1));
+	}
+
+	fn integers(source: &str) -> Vec<i32> {
+		let mut program = Program::new(source);
+		program.execute();
+		let result = program.get_result();
+		(0..result.shape()[0])
+			.map(|i| result.get(&[i]).get_integer())
+			.collect()
+	}
+
+	#[test]
+	fn current_index() {
+		assert_eq!(integers("3 1 ⊹ (1[] 2 ×) ="), [0, 2, 4]);
+		// negative indices count from the innermost array
+		assert_eq!(integers("3 1 ⊹ (1 0 - []) ="), [0, 1, 2]);
+		let mut program = Program::new("2 1 ⊹ (3 1 ⊹ (1 0 - [] 2 0 - [] 10 × +) =) =");
+		program.execute();
+		assert_eq!(program.get_result().get(&[1]).get(&[2]).get_integer(), 12);
+	}
+
+	// the values pushed for the operation of a fold are counted, 0[] being the top of the stack
+	#[test]
+	fn fold_stack_index() {
+		let run = |source| {
+			let mut program = Program::new(source);
+			program.execute();
+			program.get_result().get_integer()
+		};
+		assert_eq!(run("1 2 3 3 1 ^ 0 1 (0[] @ ~ v ~ v) ¨"), 3);
+		assert_eq!(run("1 2 3 3 1 ^ 0 1 (0[] @ ~ v +) ¨"), 6);
+	}
+
+	#[test]
+	fn loops() {
+		let run = |source| {
+			let mut program = Program::new(source);
+			program.run().map(|_| program.values().to_vec())
+		};
+		let integers = |source| {
+			let values = run(source).unwrap();
+			values.iter().map(|x| x.get_integer()).collect::<Vec<_>>()
+		};
+		assert_eq!(integers("4 (1 0 - []) times"), [0, 1, 2, 3]);
+		assert_eq!(integers("1 2 3 3 1 ^ (2 ×) each"), [2, 4, 6]);
+		assert_eq!(integers("0 (© 3 >) (1 +) while"), [3]);
+		assert_eq!(integers("0 (1 +) (© 3 ==) until"), [3]);
+		// break and continue work in the ifelse blocks and words of a loop body
+		assert_eq!(
+			integers("10 ((0) (break) 1 0 - [] 2 == ifelse v 1 0 - []) times"),
+			[0, 1]
+		);
+		assert_eq!(
+			integers("5 ((0) (continue) 1 0 - [] 2 == ifelse v 1 0 - []) times"),
+			[0, 1, 3, 4]
+		);
+		assert_eq!(
+			integers("(break) \"stop\" ≝ 0 (© 5 >) (1 + stop) while"),
+			[1]
+		);
+		assert_eq!(integers("3 (2 (1 0 - [] break) times) times"), [0, 0, 0]);
+	}
+
+	#[test]
+	fn break_outside_of_loops() {
+		let error = |source| {
+			let mut program = Program::new(source);
+			program.run().unwrap_err().message
+		};
+		assert_eq!(error("break 1 2 +"), "Cannot break outside of a loop");
+		assert_eq!(
+			error("(0) (continue) 1 1 == ifelse"),
+			"Cannot continue outside of a loop"
+		);
+		// the code of = and ¨ isn't a loop body, even inside a loop
+		assert_eq!(
+			error("2 (3 1 ⊹ (break) =) times"),
+			"Cannot break outside of a loop"
+		);
+		assert_eq!(
+			error("3 1 ⊹ 0 1 (continue) ¨"),
+			"Cannot continue outside of a loop"
+		);
+		// nor is the condition of a while
+		assert_eq!(
+			error("(break 1 1 ==) (0) while"),
+			"Cannot break outside of a loop"
+		);
+	}
+
+	#[test]
+	fn read_lines() {
+		let mut program = Program::new("");
+		let mut input = io::Cursor::new("first\r\n\nlast");
+		for _ in 0..4 {
+			push_line(&mut program, &mut input);
+		}
+		let values = program.values();
+		let lines = values
+			.iter()
+			.step_by(2)
+			.map(|x| x.get_string())
+			.collect::<Vec<_>>();
+		let read = values
+			.iter()
+			.skip(1)
+			.step_by(2)
+			.map(|x| x.get_condition())
+			.collect::<Vec<_>>();
+		assert_eq!(lines, ["first", "", "last", ""]);
+		// an empty line is told apart from the end of the input
+		assert_eq!(read, [true, true, true, false]);
+	}
+
+	#[test]
+	fn overflow_operators() {
+		let run = |source| {
+			let mut program = Program::new(source);
+			program.run().map(|_| program.get_result().get_integer())
+		};
+		// whatever the mode of the program
+		assert_eq!(run("\"saturating\" overflow 2 1073741824 ⊗"), Ok(i32::MIN));
+		assert_eq!(run("2 1073741824 ⊠"), Ok(i32::MAX));
+		assert_eq!(run("1 2147483647 0 - - 1 0 - ⊠"), Ok(i32::MAX));
+		assert_eq!(run("\"wrapping\" overflow 6 7 ⨰"), Ok(42));
+		let error = run("\"wrapping\" overflow 2 1073741824 ⨰").unwrap_err();
+		assert_eq!(error.message, "Integer overflow in 1073741824 × 2");
+		assert_eq!(run("2147483647 1 ⊕"), Ok(i32::MIN));
+		assert_eq!(run("2147483647 1 ⊞"), Ok(i32::MAX));
+		assert!(run("\"wrapping\" overflow 2147483647 1 ∔").is_err());
+	}
 }